@@ -0,0 +1,673 @@
+use crate::components::layout::Layout;
+use crate::components::stickers::{Face, Stickers};
+
+use nalgebra::Vector3;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Turn {
+    Up,
+    Down,
+    Left,
+    Right,
+    Front,
+    Back,
+    Middle,
+    Equator,
+    Standing,
+    X,
+    Y,
+    Z,
+}
+
+impl Turn {
+    pub fn axis(self) -> Vector3<f32> {
+        match self {
+            Turn::Up | Turn::Y => Vector3::z(),
+            Turn::Down | Turn::Equator => -Vector3::z(),
+            Turn::Left | Turn::Middle => Vector3::x(),
+            Turn::Right | Turn::X => -Vector3::x(),
+            Turn::Front | Turn::Z | Turn::Standing => -Vector3::y(),
+            Turn::Back => Vector3::y(),
+        }
+    }
+
+    /// The turn of a single face.
+    pub fn from_face(face: Face) -> Turn {
+        match face {
+            Face::Up => Turn::Up,
+            Face::Down => Turn::Down,
+            Face::Left => Turn::Left,
+            Face::Right => Turn::Right,
+            Face::Front => Turn::Front,
+            Face::Back => Turn::Back,
+        }
+    }
+
+    pub fn is_face(self) -> bool {
+        matches!(
+            self,
+            Turn::Up | Turn::Down | Turn::Left | Turn::Right | Turn::Front | Turn::Back
+        )
+    }
+
+    pub fn is_rotation(self) -> bool {
+        matches!(self, Turn::X | Turn::Y | Turn::Z)
+    }
+
+    fn letter(self) -> char {
+        match self {
+            Turn::Up => 'U',
+            Turn::Down => 'D',
+            Turn::Left => 'L',
+            Turn::Right => 'R',
+            Turn::Front => 'F',
+            Turn::Back => 'B',
+            Turn::Middle => 'M',
+            Turn::Equator => 'E',
+            Turn::Standing => 'S',
+            Turn::X => 'x',
+            Turn::Y => 'y',
+            Turn::Z => 'z',
+        }
+    }
+
+    // returns the turn and whether the letter implies a wide move
+    fn from_letter(c: char) -> Option<(Turn, bool)> {
+        let turn = match c {
+            'U' => (Turn::Up, false),
+            'D' => (Turn::Down, false),
+            'L' => (Turn::Left, false),
+            'R' => (Turn::Right, false),
+            'F' => (Turn::Front, false),
+            'B' => (Turn::Back, false),
+            'u' => (Turn::Up, true),
+            'd' => (Turn::Down, true),
+            'l' => (Turn::Left, true),
+            'r' => (Turn::Right, true),
+            'f' => (Turn::Front, true),
+            'b' => (Turn::Back, true),
+            'M' => (Turn::Middle, false),
+            'E' => (Turn::Equator, false),
+            'S' => (Turn::Standing, false),
+            'x' => (Turn::X, false),
+            'y' => (Turn::Y, false),
+            'z' => (Turn::Z, false),
+            _ => return None,
+        };
+        Some(turn)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub turn: Turn,
+    pub rev: bool,
+    pub double: bool,
+    pub with: bool,
+    /// The innermost layer turned counting the face as 1. A plain face turn
+    /// has depth 1, `Rw` depth 2, `3Rw` depth 3 and `2R` only turns layer 2.
+    pub depth: usize,
+}
+
+impl Move {
+    pub fn new(turn: Turn, rev: bool, double: bool, with: bool) -> Move {
+        let with = with && turn.is_face();
+        Move {
+            turn,
+            rev,
+            double,
+            with,
+            depth: if with { 2 } else { 1 },
+        }
+    }
+
+    /// Applies the changes a key's modifiers make to the move it is bound
+    /// to: reversing it, doubling it and taking the inner layers along.
+    pub fn modified(self, rev: bool, double: bool, with: bool) -> Move {
+        let mv = Move {
+            rev: self.rev != rev,
+            double: self.double || double,
+            ..self
+        };
+        if with && !mv.with && mv.depth == 1 && mv.turn.is_face() {
+            Move::new(mv.turn, mv.rev, mv.double, true)
+        } else {
+            mv
+        }
+    }
+
+    /// Sets the layer a face turn reaches, keeping it wide if it was.
+    pub fn at_depth(self, depth: usize) -> Move {
+        assert!(depth > 0);
+        if !self.turn.is_face() {
+            return self;
+        }
+        Move {
+            with: self.with && depth > 1,
+            depth,
+            ..self
+        }
+    }
+
+    // the face the move turns around and which layers from it are turned
+    fn layers(self, size: usize) -> (Face, Range<usize>) {
+        let face = match self.turn {
+            Turn::Up | Turn::Y => Face::Up,
+            Turn::Down | Turn::Equator => Face::Down,
+            Turn::Left | Turn::Middle => Face::Left,
+            Turn::Right | Turn::X => Face::Right,
+            Turn::Front | Turn::Z | Turn::Standing => Face::Front,
+            Turn::Back => Face::Back,
+        };
+
+        let layers = match self.turn {
+            _ if self.turn.is_rotation() => 0..size,
+            Turn::Middle | Turn::Equator | Turn::Standing => 1..size - 1,
+            _ if self.with => 0..self.depth.min(size),
+            _ => self.depth - 1..self.depth.min(size),
+        };
+
+        (face, layers)
+    }
+
+    pub fn inverse(self) -> Move {
+        Move {
+            rev: !self.rev,
+            ..self
+        }
+    }
+
+    pub fn apply_stickers(self, stickers: &mut Stickers) {
+        let (face, layers) = self.layers(stickers.size());
+        stickers.turn(face, layers.clone(), self.rev);
+        if self.double {
+            stickers.turn(face, layers, self.rev);
+        }
+    }
+
+    /// Moves the pieces in the layout, returning the pieces that were
+    /// turned.
+    pub fn apply_layout(self, layout: &mut Layout) -> Vec<usize> {
+        let (face, layers) = self.layers(layout.size());
+        layout.turn(face, layers.clone(), self.rev);
+        if self.double {
+            layout.turn(face, layers.clone(), self.rev);
+        }
+        layout.layers(face, layers)
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.depth != if self.with { 2 } else { 1 } {
+            write!(f, "{}", self.depth)?;
+        }
+        write!(f, "{}", self.turn.letter())?;
+        if self.with {
+            f.write_str("w")?;
+        }
+        if self.double {
+            f.write_str("2")?;
+        }
+        if self.rev {
+            f.write_str("'")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Move(Move),
+    Group(Algorithm, usize),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Algorithm {
+    steps: Vec<Step>,
+}
+
+impl Algorithm {
+    pub fn new() -> Algorithm {
+        Algorithm { steps: Vec::new() }
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    pub fn push(&mut self, mv: Move) {
+        self.steps.push(Step::Move(mv));
+    }
+
+    pub fn push_group(&mut self, alg: Algorithm, count: usize) {
+        self.steps.push(Step::Group(alg, count));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Flattens groups and repeat counts into the list of moves to execute.
+    pub fn moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        self.collect_moves(&mut moves);
+        moves
+    }
+
+    fn collect_moves(&self, moves: &mut Vec<Move>) {
+        for step in &self.steps {
+            match step {
+                Step::Move(mv) => moves.push(*mv),
+                Step::Group(alg, count) => {
+                    for _ in 0..*count {
+                        alg.collect_moves(moves);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn inverse(&self) -> Algorithm {
+        let steps = self
+            .steps
+            .iter()
+            .rev()
+            .map(|step| match step {
+                Step::Move(mv) => Step::Move(mv.inverse()),
+                Step::Group(alg, count) => Step::Group(alg.inverse(), *count),
+            })
+            .collect();
+
+        Algorithm { steps }
+    }
+
+    /// Checks that no move turns a layer deeper than a cube of `size` has.
+    /// Applying such a move turns nothing past the last layer.
+    pub fn check_depth(&self, size: usize) -> Result<(), DepthError> {
+        match self.moves().into_iter().find(|mv| mv.depth > size) {
+            Some(mv) => Err(DepthError { mv, size }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn apply_stickers(&self, stickers: &mut Stickers) {
+        for mv in self.moves() {
+            mv.apply_stickers(stickers);
+        }
+    }
+
+    pub fn apply_layout(&self, layout: &mut Layout) {
+        for mv in self.moves() {
+            mv.apply_layout(layout);
+        }
+    }
+}
+
+impl From<Vec<Move>> for Algorithm {
+    fn from(moves: Vec<Move>) -> Algorithm {
+        moves.into_iter().collect()
+    }
+}
+
+impl std::iter::FromIterator<Move> for Algorithm {
+    fn from_iter<I: IntoIterator<Item = Move>>(iter: I) -> Algorithm {
+        let steps = iter.into_iter().map(Step::Move).collect();
+        Algorithm { steps }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            match step {
+                Step::Move(mv) => write!(f, "{}", mv)?,
+                Step::Group(alg, 1) => write!(f, "({})", alg)?,
+                Step::Group(alg, count) => write!(f, "({}){}", alg, count)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A move that reaches past the last layer of the cube it is applied to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DepthError {
+    pub mv: Move,
+    pub size: usize,
+}
+
+impl fmt::Display for DepthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} turns layer {} but a {2}x{2} cube only has {2}",
+            self.mv, self.mv.depth, self.size
+        )
+    }
+}
+
+impl std::error::Error for DepthError {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnexpectedChar(char),
+    InvalidWide,
+    InvalidCount,
+    InvalidLayer,
+    UnclosedGroup,
+    UnmatchedParen,
+    /// More or less than the one move that was expected.
+    NotOneMove,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1 based character column of the offending token.
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ParseErrorKind::UnexpectedChar(c) => write!(f, "unexpected character '{}'", c)?,
+            ParseErrorKind::InvalidWide => f.write_str("only face turns can be wide")?,
+            ParseErrorKind::InvalidCount => f.write_str("invalid turn count")?,
+            ParseErrorKind::InvalidLayer => f.write_str("invalid layer")?,
+            ParseErrorKind::UnclosedGroup => f.write_str("unclosed '('")?,
+            ParseErrorKind::UnmatchedParen => f.write_str("unmatched ')'")?,
+            ParseErrorKind::NotOneMove => f.write_str("expected a single move")?,
+        }
+        write!(f, " at column {}", self.column)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error(&self, column: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            column: column + 1,
+            kind,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+
+        if start == self.pos {
+            None
+        } else {
+            let digits: String = self.chars[start..self.pos].iter().collect();
+            digits.parse().ok().or(Some(0))
+        }
+    }
+
+    fn algorithm(&mut self, depth: usize) -> Result<Algorithm, ParseError> {
+        let mut alg = Algorithm::new();
+
+        loop {
+            self.skip_whitespace();
+            let start = self.pos;
+
+            let c = match self.peek() {
+                Some(c) => c,
+                None if depth == 0 => return Ok(alg),
+                None => return Err(self.error(self.pos, ParseErrorKind::UnclosedGroup)),
+            };
+
+            match c {
+                '(' => {
+                    self.pos += 1;
+                    let group = self.algorithm(depth + 1)?;
+                    let count_pos = self.pos;
+                    let count = self.number().unwrap_or(1);
+                    if count == 0 {
+                        return Err(self.error(count_pos, ParseErrorKind::InvalidCount));
+                    }
+                    alg.push_group(group, count);
+                }
+                ')' if depth == 0 => {
+                    return Err(self.error(start, ParseErrorKind::UnmatchedParen));
+                }
+                ')' => {
+                    self.pos += 1;
+                    return Ok(alg);
+                }
+                _ => alg.push(self.parse_move()?),
+            }
+        }
+    }
+
+    fn parse_move(&mut self) -> Result<Move, ParseError> {
+        let start = self.pos;
+        let depth = self.number();
+        if depth == Some(0) {
+            return Err(self.error(start, ParseErrorKind::InvalidLayer));
+        }
+
+        let letter_pos = self.pos;
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Err(self.error(letter_pos, ParseErrorKind::InvalidLayer)),
+        };
+        let (turn, mut with) = match Turn::from_letter(c) {
+            Some(turn) => turn,
+            None => return Err(self.error(letter_pos, ParseErrorKind::UnexpectedChar(c))),
+        };
+        if depth.is_some() && !turn.is_face() {
+            return Err(self.error(start, ParseErrorKind::InvalidLayer));
+        }
+        self.pos += 1;
+
+        if self.peek() == Some('w') {
+            if with || !turn.is_face() {
+                return Err(self.error(start, ParseErrorKind::InvalidWide));
+            }
+            with = true;
+            self.pos += 1;
+        }
+
+        let count_pos = self.pos;
+        let double = match self.number() {
+            None | Some(1) => false,
+            Some(2) => true,
+            Some(_) => return Err(self.error(count_pos, ParseErrorKind::InvalidCount)),
+        };
+
+        let rev = match self.peek() {
+            Some('\'') | Some('’') => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        };
+
+        let mv = Move::new(turn, rev, double, with);
+        Ok(match depth {
+            Some(depth) => mv.at_depth(depth),
+            None => mv,
+        })
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Algorithm, ParseError> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+        };
+        parser.algorithm(0)
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Move, ParseError> {
+        match s.parse::<Algorithm>()?.steps() {
+            [Step::Move(mv)] => Ok(*mv),
+            _ => Err(ParseError {
+                column: 1,
+                kind: ParseErrorKind::NotOneMove,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_print() {
+        let s = "R U R' U2 M E' S2 x y' z Rw r' U2'";
+        let alg: Algorithm = s.parse().unwrap();
+        assert_eq!(alg.moves().len(), 13);
+        assert_eq!(alg.to_string(), "R U R' U2 M E' S2 x y' z Rw Rw' U2'");
+
+        let alg: Algorithm = "(R U R' U')3 (F)".parse().unwrap();
+        assert_eq!(alg.moves().len(), 13);
+        assert_eq!(alg.to_string(), "(R U R' U')3 (F)");
+
+        let alg: Algorithm = "RUR'U'".parse().unwrap();
+        assert_eq!(alg.to_string(), "R U R' U'");
+
+        let alg: Algorithm = "2R 3Rw' 2Rw 1R 3r2 4U".parse().unwrap();
+        assert_eq!(alg.to_string(), "2R 3Rw' Rw R 3Rw2 4U");
+        assert_eq!(alg.moves()[0].depth, 2);
+        assert!(!alg.moves()[0].with);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = "R U Q".parse::<Algorithm>().unwrap_err();
+        assert_eq!(err.column, 5);
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedChar('Q'));
+
+        let err = "R Mw".parse::<Algorithm>().unwrap_err();
+        assert_eq!(err.column, 3);
+        assert_eq!(err.kind, ParseErrorKind::InvalidWide);
+
+        let err = "R3".parse::<Algorithm>().unwrap_err();
+        assert_eq!(err.column, 2);
+
+        let err = "R 2M".parse::<Algorithm>().unwrap_err();
+        assert_eq!(err.column, 3);
+        assert_eq!(err.kind, ParseErrorKind::InvalidLayer);
+
+        let err = "R 0R".parse::<Algorithm>().unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidLayer);
+
+        let err = "(R U".parse::<Algorithm>().unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnclosedGroup);
+
+        let err = "R U)".parse::<Algorithm>().unwrap_err();
+        assert_eq!(err.column, 4);
+        assert_eq!(err.kind, ParseErrorKind::UnmatchedParen);
+
+        assert_eq!("Rw2'".parse::<Move>().unwrap().to_string(), "Rw2'");
+        let err = "R U".parse::<Move>().unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::NotOneMove);
+        assert!("(R)".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn modifiers() {
+        let r: Move = "R".parse().unwrap();
+        assert_eq!(r.modified(true, true, true).to_string(), "Rw2'");
+        let r: Move = "3Rw'".parse().unwrap();
+        assert_eq!(r.modified(true, false, true).to_string(), "3Rw");
+        let m: Move = "M".parse().unwrap();
+        assert_eq!(m.modified(false, false, true), m);
+    }
+
+    #[test]
+    fn apply() {
+        let mut stickers = Stickers::new();
+        let sexy: Algorithm = "(R U R' U')6".parse().unwrap();
+        sexy.apply_stickers(&mut stickers);
+        assert!(stickers.solved());
+
+        let alg: Algorithm = "R U2 r' F x M' y2 E S' z".parse().unwrap();
+        alg.apply_stickers(&mut stickers);
+        assert!(!stickers.solved());
+        alg.inverse().apply_stickers(&mut stickers);
+        assert!(stickers.solved());
+    }
+
+    #[test]
+    fn big_cubes() {
+        for size in 2..=7 {
+            let mut stickers = Stickers::with_size(size);
+            let alg: Algorithm = "R 2U' 3Fw2 l x M' E S y2 Dw 4B z'".parse().unwrap();
+            alg.apply_stickers(&mut stickers);
+            assert!(!stickers.solved());
+            alg.inverse().apply_stickers(&mut stickers);
+            assert!(stickers.solved(), "{}x{}", size, size);
+
+            let mut layout = Layout::with_size(size);
+            alg.apply_layout(&mut layout);
+            alg.inverse().apply_layout(&mut layout);
+            assert_eq!(
+                layout.layers(Face::Up, 0..size),
+                Layout::with_size(size).layers(Face::Up, 0..size)
+            );
+        }
+
+        let alg: Algorithm = "R (U 5Rw')2".parse().unwrap();
+        assert_eq!(alg.check_depth(5), Ok(()));
+        let err = alg.check_depth(3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "5Rw' turns layer 5 but a 3x3 cube only has 3"
+        );
+
+        // an inner slice is a wide turn undoing the outer layer
+        let mut a = Stickers::with_size(4);
+        let mut b = Stickers::with_size(4);
+        "2R".parse::<Algorithm>().unwrap().apply_stickers(&mut a);
+        "Rw R'".parse::<Algorithm>().unwrap().apply_stickers(&mut b);
+        assert_eq!(a, b);
+
+        // on a 3x3 a wide turn is the face and middle slice together
+        let mut a = Stickers::new();
+        let mut b = Stickers::new();
+        "Rw U' Fw"
+            .parse::<Algorithm>()
+            .unwrap()
+            .apply_stickers(&mut a);
+        "R M' U' F S"
+            .parse::<Algorithm>()
+            .unwrap()
+            .apply_stickers(&mut b);
+        assert_eq!(a, b);
+    }
+}
@@ -0,0 +1,690 @@
+use crate::components::algorithm::{Algorithm, Move};
+use crate::components::stickers::{Face, Stickers, FACELET_FACES};
+
+use std::fmt;
+use std::ops::Mul;
+
+pub const URF: u8 = 0;
+pub const UFL: u8 = 1;
+pub const ULB: u8 = 2;
+pub const UBR: u8 = 3;
+pub const DFR: u8 = 4;
+pub const DLF: u8 = 5;
+pub const DBL: u8 = 6;
+pub const DRB: u8 = 7;
+
+pub const UR: u8 = 0;
+pub const UF: u8 = 1;
+pub const UL: u8 = 2;
+pub const UB: u8 = 3;
+pub const DR: u8 = 4;
+pub const DF: u8 = 5;
+pub const DL: u8 = 6;
+pub const DB: u8 = 7;
+pub const FR: u8 = 8;
+pub const FL: u8 = 9;
+pub const BL: u8 = 10;
+pub const BR: u8 = 11;
+
+// facelet faces in FACELET_FACES order
+const U: usize = 0;
+const R: usize = 1;
+const F: usize = 2;
+const D: usize = 3;
+const L: usize = 4;
+const B: usize = 5;
+
+// facelets of every corner position, starting with the U or D facelet and
+// going clockwise
+static CORNER_FACELETS: [[usize; 3]; 8] = [
+    [8, 9, 20],
+    [6, 18, 38],
+    [0, 36, 47],
+    [2, 45, 11],
+    [29, 26, 15],
+    [27, 44, 24],
+    [33, 53, 42],
+    [35, 17, 51],
+];
+
+static EDGE_FACELETS: [[usize; 2]; 12] = [
+    [5, 10],
+    [7, 19],
+    [3, 37],
+    [1, 46],
+    [32, 16],
+    [28, 25],
+    [30, 43],
+    [34, 52],
+    [23, 12],
+    [21, 41],
+    [50, 39],
+    [48, 14],
+];
+
+static CORNER_COLORS: [[usize; 3]; 8] = [
+    [U, R, F],
+    [U, F, L],
+    [U, L, B],
+    [U, B, R],
+    [D, F, R],
+    [D, L, F],
+    [D, B, L],
+    [D, R, B],
+];
+
+static EDGE_COLORS: [[usize; 2]; 12] = [
+    [U, R],
+    [U, F],
+    [U, L],
+    [U, B],
+    [D, R],
+    [D, F],
+    [D, L],
+    [D, B],
+    [F, R],
+    [F, L],
+    [B, L],
+    [B, R],
+];
+
+/// Why a set of facelets can not be reached from the solved cube. Each
+/// variant holds the indices of the offending facelets in `FACELET_FACES`
+/// order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// A colour does not appear exactly nine times.
+    ColourCount(Face, Vec<usize>),
+    /// A corner or edge with a colour combination that does not exist.
+    ImpossiblePiece(Vec<usize>),
+    /// The same piece or centre colour appears twice.
+    DuplicatePiece(Vec<usize>),
+    /// The corner twists do not add up, holds the facelets of every twisted
+    /// corner.
+    TwistedCorner(Vec<usize>),
+    /// An odd number of edges are flipped, holds the facelets of every
+    /// flipped edge.
+    FlippedEdge(Vec<usize>),
+    /// An odd number of swaps is needed, holds the facelets of every
+    /// misplaced piece.
+    Parity(Vec<usize>),
+    /// Only 3x3 cubes can be checked, holds the size of the cube.
+    Unsupported(usize),
+}
+
+/// Names a facelet such as `U1` or `B9`.
+pub fn facelet_name(index: usize) -> String {
+    let face = match index / 9 {
+        U => 'U',
+        R => 'R',
+        F => 'F',
+        D => 'D',
+        L => 'L',
+        _ => 'B',
+    };
+    format!("{}{}", face, index % 9 + 1)
+}
+
+impl StateError {
+    pub fn facelets(&self) -> &[usize] {
+        match self {
+            StateError::ColourCount(_, facelets)
+            | StateError::ImpossiblePiece(facelets)
+            | StateError::DuplicatePiece(facelets)
+            | StateError::TwistedCorner(facelets)
+            | StateError::FlippedEdge(facelets)
+            | StateError::Parity(facelets) => facelets,
+            StateError::Unsupported(_) => &[],
+        }
+    }
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::ColourCount(face, facelets) => {
+                write!(f, "the {:?} colour appears {} times", face, facelets.len())?
+            }
+            StateError::ImpossiblePiece(_) => f.write_str("impossible piece")?,
+            StateError::DuplicatePiece(_) => f.write_str("duplicate piece")?,
+            StateError::TwistedCorner(_) => f.write_str("twisted corner")?,
+            StateError::FlippedEdge(_) => f.write_str("flipped edge")?,
+            StateError::Parity(_) => f.write_str("permutation parity mismatch")?,
+            StateError::Unsupported(size) => {
+                return write!(f, "only 3x3 cubes can be checked, not {0}x{0}", size)
+            }
+        }
+
+        f.write_str(" at")?;
+        for &facelet in self.facelets() {
+            write!(f, " {}", facelet_name(facelet))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for StateError {}
+
+/// The basic clockwise face turns in U, R, F, D, L, B order.
+pub static MOVES: [CubieCube; 6] = [
+    CubieCube {
+        cp: [UBR, URF, UFL, ULB, DFR, DLF, DBL, DRB],
+        co: [0, 0, 0, 0, 0, 0, 0, 0],
+        ep: [UB, UR, UF, UL, DR, DF, DL, DB, FR, FL, BL, BR],
+        eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        centers: [0, 1, 2, 3, 4, 5],
+    },
+    CubieCube {
+        cp: [DFR, UFL, ULB, URF, DRB, DLF, DBL, UBR],
+        co: [2, 0, 0, 1, 1, 0, 0, 2],
+        ep: [FR, UF, UL, UB, BR, DF, DL, DB, DR, FL, BL, UR],
+        eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        centers: [0, 1, 2, 3, 4, 5],
+    },
+    CubieCube {
+        cp: [UFL, DLF, ULB, UBR, URF, DFR, DBL, DRB],
+        co: [1, 2, 0, 0, 2, 1, 0, 0],
+        ep: [UR, FL, UL, UB, DR, FR, DL, DB, UF, DF, BL, BR],
+        eo: [0, 1, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0],
+        centers: [0, 1, 2, 3, 4, 5],
+    },
+    CubieCube {
+        cp: [URF, UFL, ULB, UBR, DLF, DBL, DRB, DFR],
+        co: [0, 0, 0, 0, 0, 0, 0, 0],
+        ep: [UR, UF, UL, UB, DF, DL, DB, DR, FR, FL, BL, BR],
+        eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        centers: [0, 1, 2, 3, 4, 5],
+    },
+    CubieCube {
+        cp: [URF, ULB, DBL, UBR, DFR, UFL, DLF, DRB],
+        co: [0, 1, 2, 0, 0, 2, 1, 0],
+        ep: [UR, UF, BL, UB, DR, DF, FL, DB, FR, UL, DL, BR],
+        eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        centers: [0, 1, 2, 3, 4, 5],
+    },
+    CubieCube {
+        cp: [URF, UFL, UBR, DRB, DFR, DLF, ULB, DBL],
+        co: [0, 0, 1, 2, 0, 0, 2, 1],
+        ep: [UR, UF, UL, BR, DR, DF, DL, BL, FR, FL, UB, DB],
+        eo: [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 1, 1],
+        centers: [0, 1, 2, 3, 4, 5],
+    },
+];
+
+/// A 3x3 cube described by its pieces rather than its stickers.
+///
+/// Pieces are identified by their colours on the solved cube and positions
+/// are fixed in space, so every turn, slice move and rotation is an element
+/// that can be composed with `multiply`. Each array maps a position to the
+/// piece that occupies it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CubieCube {
+    /// Corner permutation, indexed by `URF..DRB`.
+    pub cp: [u8; 8],
+    /// Corner twist, 0 when the U or D colour faces up or down.
+    pub co: [u8; 8],
+    /// Edge permutation, indexed by `UR..BR`.
+    pub ep: [u8; 12],
+    /// Edge flip.
+    pub eo: [u8; 12],
+    /// Centre permutation, in `FACELET_FACES` order.
+    pub centers: [u8; 6],
+}
+
+impl Default for CubieCube {
+    fn default() -> CubieCube {
+        CubieCube::SOLVED
+    }
+}
+
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+fn perm_index(perm: &[u8]) -> usize {
+    let mut index = 0;
+    for i in 0..perm.len() {
+        let smaller = perm[i + 1..].iter().filter(|&&p| p < perm[i]).count();
+        index = index * (perm.len() - i) + smaller;
+    }
+    index
+}
+
+fn set_perm_index(perm: &mut [u8], mut index: usize) {
+    let n = perm.len();
+    let mut digits = vec![0; n];
+    for i in (0..n).rev() {
+        digits[i] = index % (n - i);
+        index /= n - i;
+    }
+
+    let mut unused = (0..n as u8).collect::<Vec<_>>();
+    for i in 0..n {
+        perm[i] = unused.remove(digits[i]);
+    }
+}
+
+fn parity(perm: &[u8]) -> bool {
+    let mut odd = false;
+    for i in 0..perm.len() {
+        for j in i + 1..perm.len() {
+            if perm[j] < perm[i] {
+                odd = !odd;
+            }
+        }
+    }
+    odd
+}
+
+impl CubieCube {
+    pub const SOLVED: CubieCube = CubieCube {
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        co: [0; 8],
+        ep: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        eo: [0; 12],
+        centers: [0, 1, 2, 3, 4, 5],
+    };
+
+    /// Reads the pieces from a 3x3 cube, returning `None` for other sizes
+    /// or stickers that do not make up whole pieces.
+    pub fn from_stickers(stickers: &Stickers) -> Option<CubieCube> {
+        CubieCube::from_facelets(&stickers.colour_facelets()?)
+    }
+
+    pub fn to_stickers(self) -> Stickers {
+        Stickers::from_colour_facelets(&self.to_facelets())
+    }
+
+    /// The cube reached by applying `mv` to a solved cube.
+    pub fn from_move(mv: Move) -> CubieCube {
+        let mut stickers = Stickers::new();
+        mv.apply_stickers(&mut stickers);
+        CubieCube::from_stickers(&stickers).unwrap()
+    }
+
+    pub fn from_algorithm(alg: &Algorithm) -> CubieCube {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply(alg);
+        cube
+    }
+
+    pub fn apply_move(&mut self, mv: Move) {
+        *self = self.multiply(&CubieCube::from_move(mv));
+    }
+
+    pub fn apply(&mut self, alg: &Algorithm) {
+        for mv in alg.moves() {
+            self.apply_move(mv);
+        }
+    }
+
+    /// Builds a cube from facelets given as the face each sticker belongs to.
+    /// Returns `None` if a piece can not be identified.
+    pub fn from_facelets(facelets: &[Face; 54]) -> Option<CubieCube> {
+        CubieCube::identify(facelets).ok()
+    }
+
+    /// Like `from_facelets` but also checks that the cube can be solved,
+    /// explaining what is wrong when it can not.
+    pub fn check_facelets(facelets: &[Face; 54]) -> Result<CubieCube, StateError> {
+        let cube = CubieCube::identify(facelets)?;
+
+        if cube.co.iter().map(|&o| o as usize).sum::<usize>() % 3 != 0 {
+            let facelets = (0..8)
+                .filter(|&i| cube.co[i] != 0)
+                .flat_map(|i| CORNER_FACELETS[i].to_vec())
+                .collect();
+            return Err(StateError::TwistedCorner(facelets));
+        }
+
+        if cube.eo.iter().map(|&o| o as usize).sum::<usize>() % 2 != 0 {
+            let facelets = (0..12)
+                .filter(|&i| cube.eo[i] != 0)
+                .flat_map(|i| EDGE_FACELETS[i].to_vec())
+                .collect();
+            return Err(StateError::FlippedEdge(facelets));
+        }
+
+        if parity(&cube.cp) != parity(&cube.ep) ^ parity(&cube.centers) {
+            let corners = (0..8)
+                .filter(|&i| cube.cp[i] != i as u8)
+                .flat_map(|i| CORNER_FACELETS[i].to_vec());
+            let edges = (0..12)
+                .filter(|&i| cube.ep[i] != i as u8)
+                .flat_map(|i| EDGE_FACELETS[i].to_vec());
+            return Err(StateError::Parity(corners.chain(edges).collect()));
+        }
+
+        Ok(cube)
+    }
+
+    fn identify(facelets: &[Face; 54]) -> Result<CubieCube, StateError> {
+        let mut f = [0; 54];
+        for (i, face) in facelets.iter().enumerate() {
+            f[i] = FACELET_FACES.iter().position(|x| x == face).unwrap();
+        }
+
+        let mut cube = CubieCube::SOLVED;
+
+        let mut corners: [Option<usize>; 8] = [None; 8];
+        for (i, facelets) in CORNER_FACELETS.iter().enumerate() {
+            let impossible = || StateError::ImpossiblePiece(facelets.to_vec());
+            let ori = (0..3)
+                .find(|&o| f[facelets[o]] == U || f[facelets[o]] == D)
+                .ok_or_else(impossible)?;
+            let col1 = f[facelets[(ori + 1) % 3]];
+            let col2 = f[facelets[(ori + 2) % 3]];
+            let j = (0..8)
+                .find(|&j| CORNER_COLORS[j][1] == col1 && CORNER_COLORS[j][2] == col2)
+                .ok_or_else(impossible)?;
+
+            if let Some(other) = corners[j] {
+                let mut facelets = CORNER_FACELETS[other].to_vec();
+                facelets.extend(&CORNER_FACELETS[i]);
+                return Err(StateError::DuplicatePiece(facelets));
+            }
+            corners[j] = Some(i);
+
+            cube.cp[i] = j as u8;
+            cube.co[i] = ori as u8;
+        }
+
+        let mut edges: [Option<usize>; 12] = [None; 12];
+        for (i, facelets) in EDGE_FACELETS.iter().enumerate() {
+            let a = f[facelets[0]];
+            let b = f[facelets[1]];
+            let j = (0..12)
+                .find(|&j| {
+                    let c = &EDGE_COLORS[j];
+                    (c[0] == a && c[1] == b) || (c[0] == b && c[1] == a)
+                })
+                .ok_or_else(|| StateError::ImpossiblePiece(facelets.to_vec()))?;
+
+            if let Some(other) = edges[j] {
+                let mut facelets = EDGE_FACELETS[other].to_vec();
+                facelets.extend(&EDGE_FACELETS[i]);
+                return Err(StateError::DuplicatePiece(facelets));
+            }
+            edges[j] = Some(i);
+
+            cube.ep[i] = j as u8;
+            cube.eo[i] = (EDGE_COLORS[j][0] != a) as u8;
+        }
+
+        let mut centers: [Option<usize>; 6] = [None; 6];
+        for i in 0..6 {
+            let j = f[i * 9 + 4];
+            if let Some(other) = centers[j] {
+                return Err(StateError::DuplicatePiece(vec![other * 9 + 4, i * 9 + 4]));
+            }
+            centers[j] = Some(i);
+            cube.centers[i] = j as u8;
+        }
+
+        Ok(cube)
+    }
+
+    /// Returns the face every facelet belongs to, the inverse of
+    /// `from_facelets`.
+    pub fn to_facelets(self) -> [Face; 54] {
+        let mut f = [0; 54];
+
+        for (i, facelets) in CORNER_FACELETS.iter().enumerate() {
+            let colors = &CORNER_COLORS[self.cp[i] as usize];
+            for (n, &color) in colors.iter().enumerate() {
+                f[facelets[(n + self.co[i] as usize) % 3]] = color;
+            }
+        }
+
+        for (i, facelets) in EDGE_FACELETS.iter().enumerate() {
+            let colors = &EDGE_COLORS[self.ep[i] as usize];
+            for (n, &color) in colors.iter().enumerate() {
+                f[facelets[(n + self.eo[i] as usize) % 2]] = color;
+            }
+        }
+
+        for (i, &center) in self.centers.iter().enumerate() {
+            f[i * 9 + 4] = center as usize;
+        }
+
+        let mut out = [Face::Up; 54];
+        for (i, &face) in f.iter().enumerate() {
+            out[i] = FACELET_FACES[face];
+        }
+        out
+    }
+
+    /// Checks that the cube can be reached from the solved state.
+    pub fn is_solvable(&self) -> bool {
+        let mut corners = self.cp;
+        let mut edges = self.ep;
+        corners.sort_unstable();
+        edges.sort_unstable();
+
+        corners == CubieCube::SOLVED.cp
+            && edges == CubieCube::SOLVED.ep
+            && self.co.iter().map(|&o| o as usize).sum::<usize>() % 3 == 0
+            && self.eo.iter().map(|&o| o as usize).sum::<usize>() % 2 == 0
+            && parity(&self.cp) == parity(&self.ep) ^ parity(&self.centers)
+    }
+
+    pub fn is_solved(&self) -> bool {
+        *self == CubieCube::SOLVED
+    }
+
+    pub fn inverse(&self) -> CubieCube {
+        let mut out = CubieCube::SOLVED;
+
+        for i in 0..8 {
+            let to = self.cp[i] as usize;
+            out.cp[to] = i as u8;
+            out.co[to] = (3 - self.co[i]) % 3;
+        }
+
+        for i in 0..12 {
+            let to = self.ep[i] as usize;
+            out.ep[to] = i as u8;
+            out.eo[to] = self.eo[i];
+        }
+
+        for i in 0..6 {
+            out.centers[self.centers[i] as usize] = i as u8;
+        }
+
+        out
+    }
+
+    pub fn multiply(&self, b: &CubieCube) -> CubieCube {
+        let mut out = CubieCube::SOLVED;
+
+        for i in 0..8 {
+            let from = b.cp[i] as usize;
+            out.cp[i] = self.cp[from];
+            out.co[i] = (self.co[from] + b.co[i]) % 3;
+        }
+
+        for i in 0..12 {
+            let from = b.ep[i] as usize;
+            out.ep[i] = self.ep[from];
+            out.eo[i] = (self.eo[from] + b.eo[i]) % 2;
+        }
+
+        for i in 0..6 {
+            out.centers[i] = self.centers[b.centers[i] as usize];
+        }
+
+        out
+    }
+
+    /// Corner orientation coordinate, 0..2187.
+    pub fn twist(&self) -> usize {
+        self.co[..7].iter().fold(0, |acc, &o| acc * 3 + o as usize)
+    }
+
+    pub fn set_twist(&mut self, mut twist: usize) {
+        let mut sum = 0;
+        for i in (0..7).rev() {
+            self.co[i] = (twist % 3) as u8;
+            sum += self.co[i];
+            twist /= 3;
+        }
+        self.co[7] = (3 - sum % 3) % 3;
+    }
+
+    /// Edge orientation coordinate, 0..2048.
+    pub fn flip(&self) -> usize {
+        self.eo[..11].iter().fold(0, |acc, &o| acc * 2 + o as usize)
+    }
+
+    pub fn set_flip(&mut self, mut flip: usize) {
+        let mut sum = 0;
+        for i in (0..11).rev() {
+            self.eo[i] = (flip % 2) as u8;
+            sum += self.eo[i];
+            flip /= 2;
+        }
+        self.eo[11] = sum % 2;
+    }
+
+    /// Positions of the FR, FL, BL and BR edges ignoring their order, 0..495.
+    /// Zero when they are all in the middle slice.
+    pub fn slice(&self) -> usize {
+        let mut slice = 0;
+        let mut found = 0;
+        for j in (0..12).rev() {
+            if self.ep[j] >= FR {
+                found += 1;
+                slice += binomial(11 - j, found);
+            }
+        }
+        slice
+    }
+
+    pub fn set_slice(&mut self, slice: usize) {
+        let mut in_slice = [false; 12];
+        let mut rest = slice;
+        let mut c = 11;
+
+        for k in (1..=4).rev() {
+            while binomial(c, k) > rest {
+                c -= 1;
+            }
+            rest -= binomial(c, k);
+            in_slice[11 - c] = true;
+            c = c.saturating_sub(1);
+        }
+
+        let mut edge = FR;
+        let mut other = UR;
+        for (j, &in_slice) in in_slice.iter().enumerate() {
+            if in_slice {
+                self.ep[j] = edge;
+                edge += 1;
+            } else {
+                self.ep[j] = other;
+                other += 1;
+            }
+        }
+    }
+
+    /// Corner permutation coordinate, 0..40320.
+    pub fn corner_perm(&self) -> usize {
+        perm_index(&self.cp)
+    }
+
+    pub fn set_corner_perm(&mut self, index: usize) {
+        set_perm_index(&mut self.cp, index);
+    }
+
+    /// Permutation of the eight U and D layer edges, 0..40320. Only defined
+    /// when the middle slice edges are in the middle slice.
+    pub fn edge8_perm(&self) -> usize {
+        perm_index(&self.ep[..8])
+    }
+
+    pub fn set_edge8_perm(&mut self, index: usize) {
+        set_perm_index(&mut self.ep[..8], index);
+    }
+
+    /// Permutation of the middle slice edges, 0..24. Only defined when they
+    /// are in the middle slice.
+    pub fn slice_perm(&self) -> usize {
+        let mut perm = [0; 4];
+        for (p, &e) in perm.iter_mut().zip(&self.ep[8..]) {
+            *p = e - FR;
+        }
+        perm_index(&perm)
+    }
+
+    pub fn set_slice_perm(&mut self, index: usize) {
+        let mut perm = [0; 4];
+        set_perm_index(&mut perm, index);
+        for (e, &p) in self.ep[8..].iter_mut().zip(&perm) {
+            *e = p + FR;
+        }
+    }
+}
+
+impl Mul for CubieCube {
+    type Output = CubieCube;
+
+    fn mul(self, rhs: CubieCube) -> CubieCube {
+        self.multiply(&rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sticker_round_trip() {
+        let alg: Algorithm = "R U2 F' L D' B2 M E' S x y' z2 r u' f2".parse().unwrap();
+        let mut stickers = Stickers::new();
+        alg.apply_stickers(&mut stickers);
+
+        let cube = CubieCube::from_stickers(&stickers).unwrap();
+        assert!(cube.is_solvable());
+        assert_eq!(cube.to_stickers(), stickers);
+
+        assert_eq!(CubieCube::from_stickers(&Stickers::with_size(4)), None);
+    }
+
+    #[test]
+    fn moves_compose() {
+        let alg: Algorithm = "R U R' U' M2 E S' x' y z Lw' Dw2 B F2".parse().unwrap();
+        let mut stickers = Stickers::new();
+        alg.apply_stickers(&mut stickers);
+
+        let product = alg.moves().into_iter().fold(CubieCube::SOLVED, |cube, mv| {
+            cube * CubieCube::from_move(mv)
+        });
+        assert_eq!(CubieCube::from_stickers(&stickers), Some(product));
+        assert!((product * product.inverse()).is_solved());
+
+        for (i, &mv) in MOVES.iter().enumerate() {
+            let turn: Algorithm = ["U", "R", "F", "D", "L", "B"][i].parse().unwrap();
+            assert_eq!(CubieCube::from_algorithm(&turn), mv);
+        }
+    }
+
+    #[test]
+    fn coordinates() {
+        let mut cube = CubieCube::SOLVED;
+        for i in 0..495 {
+            cube.set_slice(i);
+            assert_eq!(cube.slice(), i);
+        }
+        for i in (0..40320).step_by(97) {
+            cube.set_corner_perm(i);
+            assert_eq!(cube.corner_perm(), i);
+        }
+        cube.set_twist(1234);
+        assert_eq!(cube.twist(), 1234);
+        cube.set_flip(777);
+        assert_eq!(cube.flip(), 777);
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+
+/// How a turn animation moves from start to end over its duration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Easing {
+    Linear,
+    #[default]
+    EaseInOut,
+    /// Goes slightly past the end and springs back.
+    Overshoot,
+}
+
+static EASINGS: [Easing; 3] = [Easing::Linear, Easing::EaseInOut, Easing::Overshoot];
+
+impl Easing {
+    /// Maps the fraction of the duration that has passed to the fraction of
+    /// the turn that should be shown. Both ends are fixed at 0 and 1.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::Overshoot => {
+                const C: f32 = 1.70158;
+                let t = t - 1.0;
+                1.0 + (C + 1.0) * t * t * t + C * t * t
+            }
+        }
+    }
+
+    /// Finds an easing by the name it is displayed with.
+    pub fn from_name(name: &str) -> Option<Easing> {
+        EASINGS.iter().copied().find(|e| e.to_string() == name)
+    }
+
+    /// The next easing, wrapping around after the last.
+    pub fn next(self) -> Easing {
+        let i = EASINGS.iter().position(|&e| e == self).unwrap();
+        EASINGS[(i + 1) % EASINGS.len()]
+    }
+}
+
+impl fmt::Display for Easing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Easing::Linear => "linear",
+            Easing::EaseInOut => "ease-in-out",
+            Easing::Overshoot => "overshoot",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ends() {
+        for &easing in &EASINGS {
+            assert!(easing.apply(0.0).abs() < 1.0e-6);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1.0e-6);
+            assert!((easing.apply(2.0) - 1.0).abs() < 1.0e-6);
+        }
+
+        assert!((Easing::EaseInOut.apply(0.5) - 0.5).abs() < 1.0e-6);
+        assert!(Easing::EaseInOut.apply(0.1) < Easing::Linear.apply(0.1));
+        assert!((0..100).any(|i| Easing::Overshoot.apply(i as f32 / 100.0) > 1.0));
+        assert_eq!(Easing::Overshoot.next(), Easing::Linear);
+        assert_eq!(Easing::from_name("ease-in-out"), Some(Easing::EaseInOut));
+        assert_eq!(Easing::from_name("bouncy"), None);
+    }
+}
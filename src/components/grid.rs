@@ -0,0 +1,217 @@
+use crate::components::stickers::Face;
+
+use nalgebra::{Matrix3, Rotation3, UnitQuaternion, Vector3};
+
+/// A point on an NxN cube with x pointing right, y up and z towards the
+/// front. Coordinates are doubled and centred so the middle of the cube is
+/// the origin for every size and each layer is two apart.
+pub type Point = [i32; 3];
+
+// the outward normal of each face plus the directions of its columns and rows
+// when the face is laid out in the standard net
+struct Frame {
+    normal: Point,
+    right: Point,
+    down: Point,
+}
+
+fn frame(face: Face) -> Frame {
+    let (normal, right, down) = match face {
+        Face::Up => ([0, 1, 0], [1, 0, 0], [0, 0, 1]),
+        Face::Down => ([0, -1, 0], [1, 0, 0], [0, 0, -1]),
+        Face::Left => ([-1, 0, 0], [0, 0, 1], [0, -1, 0]),
+        Face::Right => ([1, 0, 0], [0, 0, -1], [0, -1, 0]),
+        Face::Front => ([0, 0, 1], [1, 0, 0], [0, -1, 0]),
+        Face::Back => ([0, 0, -1], [-1, 0, 0], [0, -1, 0]),
+    };
+    Frame {
+        normal,
+        right,
+        down,
+    }
+}
+
+fn dot(a: Point, b: Point) -> i32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn normal(face: Face) -> Point {
+    frame(face).normal
+}
+
+/// The direction of increasing columns on `face` in the standard net.
+pub fn right(face: Face) -> Point {
+    frame(face).right
+}
+
+/// The direction of increasing rows on `face` in the standard net.
+pub fn down(face: Face) -> Point {
+    frame(face).down
+}
+
+pub fn face_from_normal(normal: Point) -> Face {
+    *FACES.iter().find(|&&f| frame(f).normal == normal).unwrap()
+}
+
+pub static FACES: [Face; 6] = [
+    Face::Up,
+    Face::Down,
+    Face::Left,
+    Face::Right,
+    Face::Front,
+    Face::Back,
+];
+
+/// The piece a sticker is on, given its row major index on `face`.
+pub fn sticker_point(size: usize, face: Face, index: usize) -> Point {
+    let f = frame(face);
+    let n = size as i32 - 1;
+    let row = 2 * (index / size) as i32 - n;
+    let col = 2 * (index % size) as i32 - n;
+
+    let mut p = [0; 3];
+    for (i, p) in p.iter_mut().enumerate() {
+        *p = f.normal[i] * n + f.right[i] * col + f.down[i] * row;
+    }
+    p
+}
+
+/// The row major index of the sticker on `face` belonging to the piece at
+/// `p`.
+pub fn sticker_index(size: usize, face: Face, p: Point) -> usize {
+    let f = frame(face);
+    let n = size as i32 - 1;
+    let row = (dot(p, f.down) + n) / 2;
+    let col = (dot(p, f.right) + n) / 2;
+    row as usize * size + col as usize
+}
+
+/// The point of a piece given its index, counting from the front left up
+/// corner row by row and then layer by layer towards the back.
+pub fn piece_point(size: usize, index: usize) -> Point {
+    let n = size as i32 - 1;
+    let x = (index % size) as i32;
+    let y = (index / size % size) as i32;
+    let z = (index / (size * size)) as i32;
+    [2 * x - n, n - 2 * y, n - 2 * z]
+}
+
+pub fn piece_index(size: usize, p: Point) -> usize {
+    let n = size as i32 - 1;
+    let x = (p[0] + n) / 2;
+    let y = (n - p[1]) / 2;
+    let z = (n - p[2]) / 2;
+    (z as usize * size + y as usize) * size + x as usize
+}
+
+/// How many layers in from `face` the point is, 0 being the face itself.
+pub fn depth(size: usize, face: Face, p: Point) -> usize {
+    ((size as i32 - 1 - dot(p, frame(face).normal)) / 2) as usize
+}
+
+/// Rotates a point a quarter turn about the axis of `face`, clockwise when
+/// looking at that face.
+pub fn rotate(face: Face, rev: bool, p: Point) -> Point {
+    let normal = frame(face).normal;
+    let axis = normal.iter().position(|&v| v != 0).unwrap();
+    let (b, c) = ((axis + 1) % 3, (axis + 2) % 3);
+
+    // clockwise seen from outside is a negative rotation about the normal
+    let sign = if rev { 1 } else { -1 } * normal[axis];
+
+    let mut out = p;
+    out[b] = -sign * p[c];
+    out[c] = sign * p[b];
+    out
+}
+
+/// One of the 24 rotations of the cube, stored as where it takes the x, y and
+/// z axes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Orientation([Point; 3]);
+
+impl Orientation {
+    pub const IDENTITY: Orientation = Orientation([[1, 0, 0], [0, 1, 0], [0, 0, 1]]);
+
+    /// Follows this orientation with a quarter turn about the axis of
+    /// `face`, as `rotate` does for points.
+    pub fn turn(self, face: Face, rev: bool) -> Orientation {
+        let [x, y, z] = self.0;
+        Orientation([
+            rotate(face, rev, x),
+            rotate(face, rev, y),
+            rotate(face, rev, z),
+        ])
+    }
+
+    pub fn apply(self, p: Point) -> Point {
+        let mut out = [0; 3];
+        for (axis, &v) in self.0.iter().zip(&p) {
+            for i in 0..3 {
+                out[i] += axis[i] * v;
+            }
+        }
+        out
+    }
+
+    /// The same rotation in grid space as a quaternion.
+    pub fn to_quaternion(self) -> UnitQuaternion<f32> {
+        let column = |p: Point| Vector3::new(p[0] as f32, p[1] as f32, p[2] as f32);
+        let matrix =
+            Matrix3::from_columns(&[column(self.0[0]), column(self.0[1]), column(self.0[2])]);
+        UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(matrix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for size in 2..=7 {
+            for &face in &FACES {
+                for i in 0..size * size {
+                    let p = sticker_point(size, face, i);
+                    assert_eq!(sticker_index(size, face, p), i);
+                    assert_eq!(depth(size, face, p), 0);
+                }
+            }
+            for i in 0..size * size * size {
+                assert_eq!(piece_index(size, piece_point(size, i)), i);
+            }
+        }
+
+        // U clockwise takes the front right up corner to the front left
+        let p = rotate(Face::Up, false, [2, 2, 2]);
+        assert_eq!(p, [-2, 2, 2]);
+        assert_eq!(rotate(Face::Up, true, p), [2, 2, 2]);
+        assert_eq!(
+            face_from_normal(rotate(Face::Right, false, normal(Face::Front))),
+            Face::Up
+        );
+    }
+
+    #[test]
+    fn orientations() {
+        // every combination of turns reaches exactly 24 orientations
+        let mut seen = vec![Orientation::IDENTITY];
+        let mut i = 0;
+        while i < seen.len() {
+            for &face in &[Face::Up, Face::Right, Face::Front] {
+                let o = seen[i].turn(face, false);
+                if !seen.contains(&o) {
+                    seen.push(o);
+                }
+            }
+            i += 1;
+        }
+        assert_eq!(seen.len(), 24);
+
+        let o = Orientation::IDENTITY.turn(Face::Up, false);
+        assert_eq!(o.apply([2, 2, 2]), rotate(Face::Up, false, [2, 2, 2]));
+        let q = o.to_quaternion() * Vector3::new(2.0, 2.0, 2.0);
+        assert!((q - Vector3::new(-2.0, 2.0, 2.0)).norm() < 1.0e-6);
+        assert_eq!(o.turn(Face::Up, true), Orientation::IDENTITY);
+    }
+}
@@ -1,152 +1,116 @@
-const FLU: usize = 0;
-const FU: usize = 1;
-const FRU: usize = 2;
-const FL: usize = 3;
-const F: usize = 4;
-const FR: usize = 5;
-const FLD: usize = 6;
-const FD: usize = 7;
-const FRD: usize = 8;
-
-const LU: usize = 9;
-const U: usize = 10;
-const RU: usize = 11;
-const L: usize = 12;
-const C: usize = 13;
-const R: usize = 14;
-const LD: usize = 15;
-const D: usize = 16;
-const RD: usize = 17;
-
-const BLU: usize = 18;
-const BU: usize = 19;
-const BRU: usize = 20;
-const BL: usize = 21;
-const B: usize = 22;
-const BR: usize = 23;
-const BLD: usize = 24;
-const BD: usize = 25;
-const BRD: usize = 26;
-
-static LAYOUT: [usize; 27] = [
-    FLU, FU, FRU, FL, F, FR, FLD, FD, FRD, LU, U, RU, L, C, R, LD, D, RD, BLU, BU, BRU, BL, B, BR,
-    BLD, BD, BRD,
-];
-
-pub static FRONT: Face = Face {
-    corners: [FLU, FLD, FRD, FRU],
-    edges: [FU, FL, FD, FR],
-    center: F,
-};
-
-pub static BACK: Face = Face {
-    corners: [BLU, BRU, BRD, BLD],
-    edges: [BU, BR, BD, BL],
-    center: B,
-};
-
-pub static UP: Face = Face {
-    corners: [BLU, FLU, FRU, BRU],
-    edges: [BU, LU, FU, RU],
-    center: U,
-};
-
-pub static DOWN: Face = Face {
-    corners: [BLD, BRD, FRD, FLD],
-    edges: [BD, RD, FD, LD],
-    center: D,
-};
-
-pub static LEFT: Face = Face {
-    corners: [BLU, BLD, FLD, FLU],
-    edges: [BL, LD, FL, LU],
-    center: L,
-};
-
-pub static RIGHT: Face = Face {
-    corners: [BRU, FRU, FRD, BRD],
-    edges: [BR, RU, FR, RD],
-    center: R,
-};
-
-pub static MIDDLE: Face = Face {
-    corners: [FU, BU, BD, FD],
-    edges: [F, U, B, D],
-    center: C,
-};
-
-pub static EQUATOR: Face = Face {
-    corners: [FR, FL, BL, BR],
-    edges: [F, L, B, R],
-    center: C,
-};
-
-pub static STANDING: Face = Face {
-    corners: [RU, LU, LD, RD],
-    edges: [U, L, D, R],
-    center: C,
-};
-
-#[derive(Copy, Clone)]
-pub struct Face {
-    corners: [usize; 4],
-    edges: [usize; 4],
-    center: usize,
+use crate::components::grid::{self, Orientation};
+use crate::components::stickers::{Face, MAX_SIZE, MIN_SIZE};
+
+use std::ops::Range;
+
+/// Tracks which piece is at each position of an NxN cube. Positions and
+/// pieces are numbered from the front left up corner row by row, then layer
+/// by layer towards the back, so on a solved 3x3 piece 4 is the front centre
+/// and piece 13 the core. Each piece also keeps which way it is turned.
+pub struct Layout {
+    size: usize,
+    layout: Vec<usize>,
+    // indexed by piece
+    orientations: Vec<Orientation>,
 }
 
-impl Face {
-    fn layer(&self) -> [usize; 9] {
-        let c = &self.corners;
-        let e = &self.edges;
-        [c[0], c[1], c[2], c[3], e[0], e[1], e[2], e[3], self.center]
+impl Layout {
+    pub fn new() -> Layout {
+        Layout::with_size(3)
     }
 
-    pub fn reverse(&self, rev: bool) -> Self {
-        if !rev {
-            *self
-        } else {
-            let c = &self.corners;
-            let e = &self.edges;
-
-            Face {
-                corners: [c[0], c[3], c[2], c[1]],
-                edges: [e[0], e[3], e[2], e[1]],
-                center: self.center,
+    pub fn with_size(size: usize) -> Layout {
+        assert!((MIN_SIZE..=MAX_SIZE).contains(&size));
+        Layout {
+            size,
+            layout: (0..size * size * size).collect(),
+            orientations: vec![Orientation::IDENTITY; size * size * size],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Turns the given layers a quarter turn clockwise as seen from `face`,
+    /// counting the face itself as layer 0.
+    pub fn turn(&mut self, face: Face, layers: Range<usize>, rev: bool) {
+        let n = self.size;
+        let old = self.layout.clone();
+
+        for (i, &piece) in old.iter().enumerate() {
+            let p = grid::piece_point(n, i);
+            if layers.contains(&grid::depth(n, face, p)) {
+                self.layout[grid::piece_index(n, grid::rotate(face, rev, p))] = piece;
+                self.orientations[piece] = self.orientations[piece].turn(face, rev);
             }
         }
     }
-}
 
-pub struct Layout {
-    layout: [usize; 27],
-}
+    /// How a piece has been turned since the cube was solved.
+    pub fn orientation(&self, piece: usize) -> Orientation {
+        self.orientations[piece]
+    }
 
-impl Layout {
-    pub fn new() -> Layout {
-        Layout { layout: LAYOUT }
+    /// The pieces currently in the given layers.
+    pub fn layers(&self, face: Face, layers: Range<usize>) -> Vec<usize> {
+        let n = self.size;
+        (0..self.layout.len())
+            .filter(|&i| layers.contains(&grid::depth(n, face, grid::piece_point(n, i))))
+            .map(|i| self.layout[i])
+            .collect()
     }
+}
 
-    pub fn turn(&mut self, face: &Face) {
-        let cp = self.layout;
-        let l = &mut self.layout;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let c = &face.corners;
-        let e = &face.edges;
+    #[test]
+    fn turn() {
+        let mut layout = Layout::new();
 
-        for piece in 0..4 {
-            l[c[piece]] = cp[c[(piece + 1) % c.len()]];
-            l[e[piece]] = cp[e[(piece + 1) % e.len()]];
+        // R moves the front right up corner to the back right up corner
+        layout.turn(Face::Right, 0..1, false);
+        assert_eq!(layout.layout[20], 2);
+        assert_eq!(layout.layers(Face::Right, 0..1).len(), 9);
+
+        layout.turn(Face::Right, 0..1, true);
+        assert_eq!(layout.layout, (0..27).collect::<Vec<_>>());
+
+        let mut layout = Layout::with_size(5);
+        for _ in 0..4 {
+            layout.turn(Face::Front, 1..3, false);
         }
+        assert_eq!(layout.layout, (0..125).collect::<Vec<_>>());
+        assert_eq!(layout.layers(Face::Front, 1..3).len(), 50);
     }
 
-    pub fn layer(&self, face: &Face) -> [usize; 9] {
-        let f = face.layer();
-        let l = &self.layout;
+    #[test]
+    fn long_run() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(15);
+        for size in MIN_SIZE..=MAX_SIZE {
+            let mut layout = Layout::with_size(size);
+            for _ in 0..5000 {
+                let face = grid::FACES[rng.gen_range(0, 6)];
+                let start = rng.gen_range(0, size);
+                let end = rng.gen_range(start, size) + 1;
+                layout.turn(face, start..end, rng.gen());
+            }
 
-        let mut out = [0; 9];
-        for i in 0..out.len() {
-            out[i] = l[f[i]];
+            // every piece's orientation takes it from where it started to
+            // where it is, and gives a rotation that keeps it on the grid
+            for (i, &piece) in layout.layout.iter().enumerate() {
+                let home = grid::piece_point(size, piece);
+                let o = layout.orientation(piece);
+                assert_eq!(o.apply(home), grid::piece_point(size, i));
+
+                let m = o.to_quaternion().to_rotation_matrix();
+                assert!(m.matrix().iter().all(|v| (v - v.round()).abs() < 1.0e-6));
+            }
         }
-        out
     }
 }
@@ -0,0 +1,192 @@
+use crate::components::grid::{self, Point};
+use crate::components::stickers::Face;
+
+use nalgebra::Vector3;
+
+/// Triangles ready to be uploaded to a `ModelBuffer`, with the same flat
+/// layout `tobj` uses.
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub positions: Vec<f32>,
+    pub normals: Vec<f32>,
+    pub texcoords: Vec<f32>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    fn push_vertex(&mut self, pos: Vector3<f32>, normal: Vector3<f32>) -> u32 {
+        let index = self.positions.len() / 3;
+        self.positions.extend(pos.iter());
+        self.normals.extend(normal.iter());
+        self.texcoords.extend(&[0.0, 0.0]);
+        index as u32
+    }
+}
+
+/// Shape of the generated pieces. Lengths are fractions of the distance
+/// between neighbouring pieces.
+#[derive(Debug, Copy, Clone)]
+pub struct MeshStyle {
+    /// Radius of the rounded edges of each piece.
+    pub bevel: f32,
+    /// How many quads make up each rounded edge.
+    pub bevel_segments: usize,
+    /// Space left between neighbouring pieces.
+    pub gap: f32,
+    /// Border of plastic left around each sticker.
+    pub sticker_inset: f32,
+}
+
+impl Default for MeshStyle {
+    fn default() -> MeshStyle {
+        MeshStyle {
+            bevel: 0.08,
+            bevel_segments: 3,
+            gap: 0.0125,
+            sticker_inset: 0.05,
+        }
+    }
+}
+
+impl MeshStyle {
+    // half the width of a piece
+    fn half_size(&self) -> f32 {
+        (1.0 - self.gap) / 2.0
+    }
+
+    /// A rounded cube centred on the origin for the body of a piece `size`
+    /// units wide, including the gap.
+    pub fn body(&self, size: f32) -> Mesh {
+        let h = self.half_size() * size;
+        let r = (self.bevel * size).min(h).max(0.0);
+        let segments = if r > 0.0 {
+            self.bevel_segments.max(1)
+        } else {
+            0
+        };
+
+        // the flat middle of each face plus the points along the bevels
+        let mut steps = Vec::new();
+        for k in 0..=segments {
+            steps.push(-h + r * k as f32 / segments.max(1) as f32);
+        }
+        for k in 0..=segments {
+            steps.push(h - r + r * k as f32 / segments.max(1) as f32);
+        }
+
+        let mut mesh = Mesh::default();
+        for &face in &grid::FACES {
+            let normal = vector(grid::normal(face));
+            let right = vector(grid::right(face));
+            let down = vector(grid::down(face));
+            let start = mesh.positions.len() as u32 / 3;
+
+            for &b in &steps {
+                for &a in &steps {
+                    let p = normal * h + right * a + down * b;
+                    // round the box by pushing every point out from the
+                    // inner box shrunk by the bevel radius
+                    let inner = p.map(|v| v.max(r - h).min(h - r));
+                    let offset = p - inner;
+                    let n = if offset.norm() > 1e-6 {
+                        offset.normalize()
+                    } else {
+                        normal
+                    };
+                    mesh.push_vertex(inner + n * r, n);
+                }
+            }
+
+            let w = steps.len() as u32;
+            for j in 0..w - 1 {
+                for i in 0..w - 1 {
+                    let v = start + j * w + i;
+                    mesh.indices
+                        .extend(&[v, v + w, v + 1, v + 1, v + w, v + w + 1]);
+                }
+            }
+        }
+
+        mesh
+    }
+
+    /// A sticker on `face` of a piece `size` units wide, sitting just above
+    /// the body.
+    pub fn sticker(&self, size: f32, face: Face) -> Mesh {
+        let h = self.half_size() * size;
+        let s = (h - self.sticker_inset * size).max(0.0);
+        let lift = h + 0.005 * size;
+
+        let normal = vector(grid::normal(face));
+        let right = vector(grid::right(face));
+        let down = vector(grid::down(face));
+
+        let mut mesh = Mesh::default();
+        for &(a, b) in &[(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+            mesh.push_vertex(normal * lift + right * (a * s) + down * (b * s), normal);
+        }
+        mesh.indices.extend(&[0, 2, 1, 1, 2, 3]);
+        mesh
+    }
+}
+
+fn vector(p: Point) -> Vector3<f32> {
+    Vector3::new(p[0] as f32, p[1] as f32, p[2] as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertices(v: &[f32]) -> Vec<Vector3<f32>> {
+        v.chunks(3)
+            .map(|v| Vector3::new(v[0], v[1], v[2]))
+            .collect()
+    }
+
+    // every triangle should face the same way as its vertex normals
+    fn outward(mesh: &Mesh) -> bool {
+        let pos = vertices(&mesh.positions);
+        let normals = vertices(&mesh.normals);
+        mesh.indices.chunks(3).all(|t| {
+            let (a, b, c) = (t[0] as usize, t[1] as usize, t[2] as usize);
+            let n = (pos[b] - pos[a]).cross(&(pos[c] - pos[a]));
+            n.norm() < 1e-9 || n.dot(&(normals[a] + normals[b] + normals[c])) > 0.0
+        })
+    }
+
+    #[test]
+    fn body() {
+        let style = MeshStyle::default();
+        let mesh = style.body(2.0);
+        let h = style.half_size() * 2.0;
+
+        assert_eq!(mesh.positions.len(), mesh.normals.len());
+        assert_eq!(mesh.positions.len() / 3 * 2, mesh.texcoords.len());
+        for p in vertices(&mesh.positions) {
+            assert!(p.iter().all(|v| v.abs() <= h + 1e-5));
+        }
+        for n in vertices(&mesh.normals) {
+            assert!((n.norm() - 1.0).abs() < 1e-5);
+        }
+        assert!(outward(&mesh));
+
+        let square = MeshStyle {
+            bevel: 0.0,
+            ..style
+        };
+        assert_eq!(square.body(2.0).indices.len(), 6 * 6);
+    }
+
+    #[test]
+    fn sticker() {
+        let style = MeshStyle::default();
+        for &face in &grid::FACES {
+            let mesh = style.sticker(2.0, face);
+            assert!(outward(&mesh));
+            for p in vertices(&mesh.positions) {
+                assert!(p.dot(&vector(grid::normal(face))) > style.half_size() * 2.0);
+            }
+        }
+    }
+}
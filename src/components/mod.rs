@@ -1,4 +1,11 @@
+pub mod algorithm;
+pub mod cubie;
+pub mod easing;
+pub mod grid;
 pub mod layout;
-pub mod piece;
+pub mod mesh;
+pub mod picking;
+pub mod scheme;
 pub mod stickers;
 pub mod transform;
+pub mod undo;
@@ -0,0 +1,206 @@
+use crate::components::algorithm::{Move, Turn};
+use crate::components::grid::{self, Point};
+use crate::components::stickers::Face;
+
+use nalgebra::{Matrix4, Point3, Vector3};
+
+// how far the cursor has to move across a face, in grid units, before a drag
+// picks a turn. Neighbouring layers are two units apart.
+const DRAG_THRESHOLD: f32 = 0.75;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    pub dir: Vector3<f32>,
+}
+
+impl Ray {
+    pub fn new(origin: Point3<f32>, dir: Vector3<f32>) -> Ray {
+        Ray {
+            origin,
+            dir: dir.normalize(),
+        }
+    }
+
+    /// Moves the ray into the space described by `m`, usually the inverse of
+    /// a model matrix.
+    pub fn transform(&self, m: &Matrix4<f32>) -> Ray {
+        Ray::new(
+            m.transform_point(&self.origin),
+            m.transform_vector(&self.dir),
+        )
+    }
+
+    pub fn at(&self, t: f32) -> Point3<f32> {
+        self.origin + self.dir * t
+    }
+}
+
+/// Where a ray in grid space first hits the outside of the cube.
+#[derive(Debug, Copy, Clone)]
+pub struct Hit {
+    pub face: Face,
+    /// The piece under the cursor.
+    pub piece: Point,
+    pub pos: Point3<f32>,
+}
+
+fn cross(a: Point, b: Point) -> Point {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn axis(face: Face) -> usize {
+    grid::normal(face).iter().position(|&v| v != 0).unwrap()
+}
+
+/// Casts a ray in grid space, where the cube spans `-size..size` on every
+/// axis, against the outside of the cube.
+pub fn pick(size: usize, ray: &Ray) -> Option<Hit> {
+    let extent = size as f32;
+    let (mut near, mut far) = (f32::NEG_INFINITY, f32::INFINITY);
+    let mut normal = [0; 3];
+
+    for i in 0..3 {
+        if ray.dir[i].abs() < f32::EPSILON {
+            if ray.origin[i].abs() > extent {
+                return None;
+            }
+            continue;
+        }
+
+        let t1 = (-extent - ray.origin[i]) / ray.dir[i];
+        let t2 = (extent - ray.origin[i]) / ray.dir[i];
+        if t1.min(t2) > near {
+            near = t1.min(t2);
+            normal = [0; 3];
+            normal[i] = if ray.dir[i] > 0.0 { -1 } else { 1 };
+        }
+        far = far.min(t1.max(t2));
+    }
+
+    if near > far || near < 0.0 {
+        return None;
+    }
+
+    let face = grid::face_from_normal(normal);
+    let pos = ray.at(near);
+    let n = size as i32 - 1;
+    let mut piece = [0; 3];
+    for i in 0..3 {
+        piece[i] = if normal[i] != 0 {
+            normal[i] * n
+        } else {
+            let layer = ((pos[i] + extent) / 2.0).floor() as i32;
+            2 * layer.max(0).min(n) - n
+        };
+    }
+
+    Some(Hit { face, piece, pos })
+}
+
+/// The turn made by dragging from `hit` to where `ray` crosses the plane of
+/// the face that was hit, once the drag is long enough to tell its direction.
+pub fn drag_move(size: usize, hit: &Hit, ray: &Ray) -> Option<Move> {
+    let a = axis(hit.face);
+    if ray.dir[a].abs() < f32::EPSILON {
+        return None;
+    }
+    let t = (hit.pos[a] - ray.origin[a]) / ray.dir[a];
+    if t < 0.0 {
+        return None;
+    }
+
+    let mut drag = ray.at(t) - hit.pos;
+    drag[a] = 0.0;
+    let i = drag.iamax();
+    if drag[i].abs() < DRAG_THRESHOLD {
+        return None;
+    }
+
+    let mut dir = [0; 3];
+    dir[i] = drag[i].signum() as i32;
+
+    // turning clockwise around `face` moves the sticker along the drag
+    let axis = cross(grid::normal(hit.face), dir);
+    let face = grid::face_from_normal([-axis[0], -axis[1], -axis[2]]);
+    let depth = grid::depth(size, face, hit.piece);
+
+    Some(layer_move(size, face, depth, false))
+}
+
+// names a single layer turn from the nearer face, or as a slice move for the
+// middle layer of a 3x3
+fn layer_move(size: usize, face: Face, depth: usize, rev: bool) -> Move {
+    if 2 * depth > size - 1 {
+        let n = grid::normal(face);
+        let opposite = grid::face_from_normal([-n[0], -n[1], -n[2]]);
+        return layer_move(size, opposite, size - 1 - depth, !rev);
+    }
+
+    if size == 3 && depth == 1 {
+        let (slice, rev) = match face {
+            Face::Left => (Turn::Middle, rev),
+            Face::Right => (Turn::Middle, !rev),
+            Face::Down => (Turn::Equator, rev),
+            Face::Up => (Turn::Equator, !rev),
+            Face::Front => (Turn::Standing, rev),
+            Face::Back => (Turn::Standing, !rev),
+        };
+        return Move::new(slice, rev, false, false);
+    }
+
+    Move::new(Turn::from_face(face), rev, false, false).at_depth(depth + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn front_ray(x: f32, y: f32) -> Ray {
+        Ray::new(Point3::new(x, y, 10.0), -Vector3::z())
+    }
+
+    fn drag(size: usize, from: (f32, f32), to: (f32, f32)) -> Option<String> {
+        let hit = pick(size, &front_ray(from.0, from.1)).unwrap();
+        drag_move(size, &hit, &front_ray(to.0, to.1)).map(|mv| mv.to_string())
+    }
+
+    #[test]
+    fn pick_face() {
+        let hit = pick(3, &front_ray(0.0, 0.0)).unwrap();
+        assert_eq!(hit.face, Face::Front);
+        assert_eq!(hit.piece, [0, 0, 2]);
+
+        let hit = pick(3, &front_ray(2.5, 2.5)).unwrap();
+        assert_eq!(hit.piece, [2, 2, 2]);
+
+        let hit = pick(2, &Ray::new(Point3::new(0.5, 10.0, -1.5), -Vector3::y())).unwrap();
+        assert_eq!(hit.face, Face::Up);
+        assert_eq!(hit.piece, [1, 1, -1]);
+
+        assert!(pick(3, &front_ray(3.5, 0.0)).is_none());
+        assert!(pick(3, &Ray::new(Point3::new(0.0, 0.0, 10.0), Vector3::z())).is_none());
+    }
+
+    #[test]
+    fn drag_turns() {
+        assert_eq!(drag(3, (0.0, 0.0), (0.2, 0.3)), None);
+
+        // dragging the rows of the front face to the right
+        assert_eq!(drag(3, (0.0, 2.0), (2.0, 2.0)).unwrap(), "U'");
+        assert_eq!(drag(3, (0.0, 0.0), (2.0, 0.0)).unwrap(), "E");
+        assert_eq!(drag(3, (0.0, -2.0), (2.0, -2.0)).unwrap(), "D");
+
+        // and the columns up
+        assert_eq!(drag(3, (2.0, 0.0), (2.0, 2.0)).unwrap(), "R");
+        assert_eq!(drag(3, (0.0, 0.0), (0.0, 2.0)).unwrap(), "M'");
+        assert_eq!(drag(3, (-2.0, 0.0), (-2.0, 2.0)).unwrap(), "L'");
+
+        assert_eq!(drag(4, (1.0, 0.0), (1.0, -2.0)).unwrap(), "2R'");
+        assert_eq!(drag(5, (0.0, 0.0), (-2.0, 0.0)).unwrap(), "3U");
+    }
+}
@@ -1,33 +0,0 @@
-//corners
-pub const BRW: [usize; 4] = [26, 16, 47, 73];
-pub const BOW: [usize; 4] = [28, 31, 18, 1];
-pub const BOY: [usize; 4] = [21, 34, 5, 67];
-pub const BRY: [usize; 4] = [23, 54, 3, 72];
-pub const GRY: [usize; 4] = [39, 52, 10, 71];
-pub const GRW: [usize; 4] = [46, 49, 13, 70];
-pub const GOW: [usize; 4] = [44, 29, 11, 69];
-pub const GOY: [usize; 4] = [41, 36, 8, 68];
-
-//edges
-pub const BY: [usize; 3] = [22, 4, 56];
-pub const BR: [usize; 3] = [25, 55, 60];
-pub const BW: [usize; 3] = [27, 17, 57];
-pub const BO: [usize; 3] = [20, 32, 0];
-pub const GO: [usize; 3] = [43, 37, 59];
-pub const GY: [usize; 3] = [40, 9, 75];
-pub const GR: [usize; 3] = [38, 50, 74];
-pub const GW: [usize; 3] = [45, 12, 58];
-pub const OW: [usize; 3] = [19, 30, 64];
-pub const OY: [usize; 3] = [35, 7, 65];
-pub const RY: [usize; 3] = [53, 2, 76];
-pub const RW: [usize; 3] = [48, 14, 63];
-
-//centers
-pub const W: [usize; 2] = [15, 79];
-pub const O: [usize; 2] = [33, 77];
-pub const Y: [usize; 2] = [6, 66];
-pub const R: [usize; 2] = [51, 61];
-pub const B: [usize; 2] = [24, 78];
-pub const G: [usize; 2] = [42, 62];
-
-pub const C: [usize; 1] = [80];
@@ -0,0 +1,230 @@
+use crate::components::stickers::{Face, Sticker, FACELET_FACES, STICKERS};
+
+use std::fmt;
+
+pub type Colour = [f32; 3];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemeError {
+    /// Not a face letter followed by `=`.
+    InvalidEntry(String),
+    /// A colour that is not written as `#rrggbb`.
+    InvalidColour(String),
+    /// No colour was given for the face.
+    Missing(Face),
+}
+
+impl fmt::Display for SchemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchemeError::InvalidEntry(s) => write!(f, "expected FACE=#rrggbb, found {:?}", s),
+            SchemeError::InvalidColour(s) => write!(f, "invalid colour {:?}", s),
+            SchemeError::Missing(face) => write!(f, "no colour for face {}", face.letter()),
+        }
+    }
+}
+
+impl std::error::Error for SchemeError {}
+
+/// The colour each sticker is drawn in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColourScheme {
+    pub name: String,
+    // indexed by `Sticker`
+    colours: [Colour; 6],
+}
+
+impl ColourScheme {
+    /// Creates a scheme from the colours of the stickers whose home is each
+    /// face, in `Face` order.
+    pub fn new<S: Into<String>>(name: S, faces: [Colour; 6]) -> ColourScheme {
+        let mut colours = [[0.0; 3]; 6];
+        for &sticker in &STICKERS {
+            colours[sticker as usize] = faces[sticker.home() as usize];
+        }
+        ColourScheme {
+            name: name.into(),
+            colours,
+        }
+    }
+
+    /// Yellow opposite white, blue opposite green and red opposite orange.
+    pub fn western() -> ColourScheme {
+        ColourScheme::new(
+            "western",
+            [
+                [0.8, 0.538, 0.0],
+                [0.8, 0.8, 0.8],
+                [0.8, 0.122, 0.0],
+                [0.532, 0.0, 0.0],
+                [0.018, 0.098, 0.8],
+                [0.102, 0.578, 0.007],
+            ],
+        )
+    }
+
+    /// Yellow opposite green, blue opposite white and red opposite orange.
+    pub fn japanese() -> ColourScheme {
+        ColourScheme::new(
+            "japanese",
+            [
+                [0.8, 0.538, 0.0],
+                [0.102, 0.578, 0.007],
+                [0.8, 0.122, 0.0],
+                [0.532, 0.0, 0.0],
+                [0.018, 0.098, 0.8],
+                [0.8, 0.8, 0.8],
+            ],
+        )
+    }
+
+    /// Fully saturated colours that stay apart on dim screens.
+    pub fn high_contrast() -> ColourScheme {
+        ColourScheme::new(
+            "high-contrast",
+            [
+                [1.0, 1.0, 0.0],
+                [1.0, 1.0, 1.0],
+                [1.0, 0.45, 0.0],
+                [0.75, 0.0, 0.0],
+                [0.0, 0.2, 1.0],
+                [0.0, 0.85, 0.0],
+            ],
+        )
+    }
+
+    /// The Okabe-Ito palette, which can be told apart with the common forms
+    /// of colour blindness. Red is shown as purple.
+    pub fn colour_blind() -> ColourScheme {
+        ColourScheme::new(
+            "colour-blind",
+            [
+                [0.941, 0.894, 0.259],
+                [1.0, 1.0, 1.0],
+                [0.902, 0.624, 0.0],
+                [0.8, 0.475, 0.655],
+                [0.0, 0.447, 0.698],
+                [0.0, 0.62, 0.451],
+            ],
+        )
+    }
+
+    /// The schemes that come with the app, the default first.
+    pub fn builtin() -> Vec<ColourScheme> {
+        vec![
+            ColourScheme::western(),
+            ColourScheme::japanese(),
+            ColourScheme::high_contrast(),
+            ColourScheme::colour_blind(),
+        ]
+    }
+
+    pub fn by_name(name: &str) -> Option<ColourScheme> {
+        ColourScheme::builtin().into_iter().find(|s| s.name == name)
+    }
+
+    /// Reads a scheme written as the colour of each face on a solved cube,
+    /// such as `U=#ffffff,D=#ffd500,L=#ff5800,R=#b71234,F=#009b48,B=#0046ad`.
+    pub fn parse<S: Into<String>>(name: S, spec: &str) -> Result<ColourScheme, SchemeError> {
+        let mut faces = [None; 6];
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let invalid = || SchemeError::InvalidEntry(entry.to_string());
+            let mut parts = entry.splitn(2, '=');
+            let mut letter = parts.next().unwrap().trim().chars();
+            let face = match (letter.next().and_then(Face::from_letter), letter.next()) {
+                (Some(face), None) => face,
+                _ => return Err(invalid()),
+            };
+            let colour = parts.next().ok_or_else(invalid)?.trim();
+            faces[face as usize] = Some(parse_colour(colour)?);
+        }
+
+        let mut colours = [[0.0; 3]; 6];
+        for &face in &FACELET_FACES {
+            colours[face as usize] = faces[face as usize].ok_or(SchemeError::Missing(face))?;
+        }
+        Ok(ColourScheme::new(name, colours))
+    }
+
+    pub fn colour(&self, sticker: Sticker) -> Colour {
+        self.colours[sticker as usize]
+    }
+
+    pub fn set_colour(&mut self, sticker: Sticker, colour: Colour) {
+        self.colours[sticker as usize] = colour;
+    }
+}
+
+impl Default for ColourScheme {
+    fn default() -> ColourScheme {
+        ColourScheme::western()
+    }
+}
+
+/// Reads a colour written as `#rrggbb`.
+pub fn parse_colour(s: &str) -> Result<Colour, SchemeError> {
+    let invalid = || SchemeError::InvalidColour(s.to_string());
+    let hex = s.strip_prefix('#').ok_or_else(invalid)?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+
+    let mut colour = [0.0; 3];
+    for (i, c) in colour.iter_mut().enumerate() {
+        let byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+        *c = f32::from(byte) / 255.0;
+    }
+    Ok(colour)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin() {
+        for scheme in ColourScheme::builtin() {
+            for (i, &a) in STICKERS.iter().enumerate() {
+                for &b in &STICKERS[i + 1..] {
+                    assert_ne!(scheme.colour(a), scheme.colour(b), "{}", scheme.name);
+                }
+            }
+            assert_eq!(ColourScheme::by_name(&scheme.name), Some(scheme));
+        }
+
+        let western = ColourScheme::western();
+        let japanese = ColourScheme::japanese();
+        assert_eq!(
+            western.colour(Sticker::Yellow),
+            japanese.colour(Sticker::Yellow)
+        );
+        assert_eq!(
+            western.colour(Sticker::White),
+            japanese.colour(Sticker::Green)
+        );
+        assert_eq!(ColourScheme::by_name("neon"), None);
+    }
+
+    #[test]
+    fn parse() {
+        let spec = "U=#ffffff, D=#ffd500,L=#ff5800,R=#b71234,F=#009b48,B=#0046ad";
+        let scheme = ColourScheme::parse("mine", spec).unwrap();
+        assert_eq!(scheme.colour(Sticker::Yellow), [1.0; 3]);
+        assert_eq!(
+            scheme.colour(Sticker::Green),
+            [0.0, 70.0 / 255.0, 173.0 / 255.0]
+        );
+
+        assert_eq!(
+            ColourScheme::parse("mine", "U=#ffffff"),
+            Err(SchemeError::Missing(Face::Right))
+        );
+        assert_eq!(
+            ColourScheme::parse("mine", "X=#ffffff"),
+            Err(SchemeError::InvalidEntry("X=#ffffff".to_string()))
+        );
+        assert!(parse_colour("#fff").is_err());
+        assert!(parse_colour("ffffff").is_err());
+        assert!(parse_colour("#gggggg").is_err());
+    }
+}
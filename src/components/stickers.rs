@@ -1,35 +1,28 @@
-use std::fmt;
-use std::mem::swap;
-use std::ops::Neg;
-
-static SLICE_UP: [usize; 3] = [1, 8, 5];
-static SLICE_RIGHT: [usize; 3] = [7, 8, 3];
-static SLICE_DOWN: [usize; 3] = [5, 8, 1];
-static SLICE_LEFT: [usize; 3] = [3, 8, 7];
-
-#[derive(Debug, Copy, Clone)]
-enum Direction {
-    Up = 0,
-    Right = 2,
-    Down = 4,
-    Left = 6,
-}
+use crate::components::cubie::{CubieCube, StateError};
 
-impl Neg for Direction {
-    type Output = Direction;
-
-    fn neg(self) -> Self::Output {
-        match self {
-            Self::Up => Self::Down,
-            Self::Down => Self::Up,
-            Self::Left => Self::Right,
-            Self::Right => Self::Left,
-        }
-    }
-}
+use crate::components::grid;
 
+use std::fmt;
+use std::ops::Range;
+
+// the largest and smallest supported puzzles
+pub const MIN_SIZE: usize = 2;
+pub const MAX_SIZE: usize = 7;
+
+/// Face order of the 54 facelet ordering used by most other cube tools.
+pub static FACELET_FACES: [Face; 6] = [
+    Face::Up,
+    Face::Right,
+    Face::Front,
+    Face::Down,
+    Face::Left,
+    Face::Back,
+];
+
+/// A sticker, named after its colour in the standard scheme. Colour schemes
+/// can show it in another colour.
 #[derive(Copy, Clone, PartialEq, Eq)]
-enum Sticker {
+pub enum Sticker {
     Red,
     Orange,
     Blue,
@@ -52,7 +45,71 @@ impl fmt::Debug for Sticker {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+/// Every sticker in declaration order.
+pub static STICKERS: [Sticker; 6] = [
+    Sticker::Red,
+    Sticker::Orange,
+    Sticker::Blue,
+    Sticker::Green,
+    Sticker::White,
+    Sticker::Yellow,
+];
+
+impl Sticker {
+    /// The face this sticker belongs to on a solved cube.
+    pub fn home(self) -> Face {
+        match self {
+            Self::Yellow => Face::Up,
+            Self::White => Face::Down,
+            Self::Orange => Face::Left,
+            Self::Red => Face::Right,
+            Self::Blue => Face::Front,
+            Self::Green => Face::Back,
+        }
+    }
+
+    pub fn from_home(face: Face) -> Sticker {
+        match face {
+            Face::Up => Self::Yellow,
+            Face::Down => Self::White,
+            Face::Left => Self::Orange,
+            Face::Right => Self::Red,
+            Face::Front => Self::Blue,
+            Face::Back => Self::Green,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FaceletError {
+    /// The string was not 54 characters long.
+    Length(usize),
+    /// A character at the given 0 based index is not a face letter.
+    InvalidChar(usize, char),
+    /// The facelets do not describe a solvable cube.
+    State(StateError),
+    /// Only 3x3 cubes can be written as facelets.
+    Unsupported(usize),
+}
+
+impl fmt::Display for FaceletError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FaceletError::Length(len) => write!(f, "expected 54 facelets, got {}", len),
+            FaceletError::InvalidChar(i, c) => {
+                write!(f, "unexpected character '{}' at facelet {}", c, i + 1)
+            }
+            FaceletError::State(e) => e.fmt(f),
+            FaceletError::Unsupported(size) => {
+                write!(f, "only 3x3 cubes have facelets, not {0}x{0}", size)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FaceletError {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Face {
     Up,
     Down,
@@ -63,38 +120,19 @@ pub enum Face {
 }
 
 impl Face {
-    fn intersect(self, face: Face) -> Direction {
+    pub fn letter(self) -> char {
         match self {
-            Self::Up => Direction::Up,
-            Self::Down => Direction::Down,
-            Self::Front => match face {
-                Self::Up => Direction::Down,
-                Self::Right => Direction::Left,
-                Self::Down => Direction::Up,
-                Self::Left => Direction::Right,
-                _ => unreachable!(),
-            },
-            Self::Back => -Self::intersect(Self::Front, face),
-            Self::Left => match face {
-                Self::Up => Direction::Left,
-                Self::Front => Direction::Left,
-                Self::Down => Direction::Left,
-                Self::Back => Direction::Right,
-                _ => unreachable!(),
-            },
-            Self::Right => -Self::intersect(Self::Left, face),
+            Self::Up => 'U',
+            Self::Down => 'D',
+            Self::Left => 'L',
+            Self::Right => 'R',
+            Self::Front => 'F',
+            Self::Back => 'B',
         }
     }
 
-    fn surrounding(self) -> [Face; 4] {
-        match self {
-            Self::Up => [Self::Front, Self::Right, Self::Back, Self::Left],
-            Self::Left => [Self::Up, Self::Back, Self::Down, Self::Front],
-            Self::Front => [Self::Up, Self::Left, Self::Down, Self::Right],
-            Self::Down => [Self::Front, Self::Left, Self::Back, Self::Right],
-            Self::Right => [Self::Up, Self::Front, Self::Down, Self::Back],
-            Self::Back => [Self::Up, Self::Right, Self::Down, Self::Left],
-        }
+    pub fn from_letter(c: char) -> Option<Face> {
+        FACELET_FACES.iter().copied().find(|f| f.letter() == c)
     }
 }
 
@@ -105,75 +143,39 @@ pub enum Slice {
     Standing,
 }
 
-impl Slice {
-    fn intersect(self, face: Face) -> [usize; 3] {
-        match self {
-            Self::Equator => SLICE_RIGHT,
-            Self::Middle => match face {
-                Face::Front => SLICE_DOWN,
-                Face::Up => SLICE_DOWN,
-                Face::Back => SLICE_UP,
-                Face::Down => SLICE_UP,
-                _ => unreachable!(),
-            },
-            Self::Standing => match face {
-                Face::Up => SLICE_RIGHT,
-                Face::Left => SLICE_DOWN,
-                Face::Down => SLICE_LEFT,
-                Face::Right => SLICE_UP,
-                _ => unreachable!(),
-            },
-        }
-    }
-
-    fn surrounding(self) -> [Face; 4] {
-        match self {
-            Self::Middle => Face::Left.surrounding(),
-            Self::Equator => Face::Down.surrounding(),
-            Self::Standing => Face::Front.surrounding(),
-        }
-    }
-}
-
 pub enum Rotate {
     X,
     Y,
     Z,
 }
 
+#[derive(Clone, PartialEq, Eq)]
 pub struct Stickers {
-    stickers: [[Sticker; 9]; 6],
+    size: usize,
+    // each face is stored row by row as it appears in the standard net
+    stickers: [Vec<Sticker>; 6],
 }
 
 impl fmt::Debug for Stickers {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let n = self.size;
         let l = &self.stickers;
+        let indent = " ".repeat(n * 2 + 2);
 
-        const P0: usize = 0;
-        const P1: usize = 1;
-        const P2: usize = 2;
-        const P3: usize = 7;
-        const P4: usize = 8;
-        const P5: usize = 3;
-        const P6: usize = 6;
-        const P7: usize = 5;
-        const P8: usize = 4;
-        static PINDEX: [usize; 9] = [P0, P1, P2, P3, P4, P5, P6, P7, P8];
-
-        for y in 0..3 {
-            f.write_str("        ")?;
-            for x in 0..3 {
-                write!(f, "{:?} ", l[Face::Up as usize][PINDEX[x + (3 * y)]])?;
+        for y in 0..n {
+            f.write_str(&indent)?;
+            for x in 0..n {
+                write!(f, "{:?} ", l[Face::Up as usize][x + n * y])?;
             }
             f.write_str("  \n")?;
         }
 
         f.write_str("\n")?;
 
-        for y in 0..3 {
+        for y in 0..n {
             for &face in &[Face::Left, Face::Front, Face::Right, Face::Back] {
-                for x in 0..3 {
-                    write!(f, "{:?} ", l[face as usize][PINDEX[x + (3 * y)]])?;
+                for x in 0..n {
+                    write!(f, "{:?} ", l[face as usize][x + n * y])?;
                 }
                 f.write_str("  ")?;
             }
@@ -182,10 +184,10 @@ impl fmt::Debug for Stickers {
 
         f.write_str("\n")?;
 
-        for y in 0..3 {
-            f.write_str("        ")?;
-            for x in 0..3 {
-                write!(f, "{:?} ", l[Face::Down as usize][PINDEX[x + (3 * y)]])?;
+        for y in 0..n {
+            f.write_str(&indent)?;
+            for x in 0..n {
+                write!(f, "{:?} ", l[Face::Down as usize][x + n * y])?;
             }
             f.write_str("  \n")?;
         }
@@ -194,19 +196,32 @@ impl fmt::Debug for Stickers {
 }
 
 impl Stickers {
-    pub const fn new() -> Self {
+    pub fn new() -> Self {
+        Stickers::with_size(3)
+    }
+
+    /// Creates a solved NxN cube.
+    pub fn with_size(size: usize) -> Self {
+        assert!((MIN_SIZE..=MAX_SIZE).contains(&size));
+
+        let face = |face| vec![Sticker::from_home(face); size * size];
         Stickers {
+            size,
             stickers: [
-                [Sticker::Yellow; 9],
-                [Sticker::White; 9],
-                [Sticker::Orange; 9],
-                [Sticker::Red; 9],
-                [Sticker::Blue; 9],
-                [Sticker::Green; 9],
+                face(Face::Up),
+                face(Face::Down),
+                face(Face::Left),
+                face(Face::Right),
+                face(Face::Front),
+                face(Face::Back),
             ],
         }
     }
 
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
     pub fn solved(&self) -> bool {
         for face in &self.stickers {
             if !face.iter().skip(1).all(|&x| x == face[0]) {
@@ -216,111 +231,161 @@ impl Stickers {
         true
     }
 
-    pub fn face(&mut self, face: Face, rev: bool) {
-        self.rotate_face(face, rev);
-        let surrounding = face.surrounding();
-        if rev {
-            for n in (0..3).rev() {
-                self.swap_sides(face, surrounding[n], surrounding[n + 1]);
+    /// Returns the face every facelet belongs to, going by the colour of the
+    /// centres, in `FACELET_FACES` order with each face read row by row.
+    /// Only 3x3 cubes have facelets, `None` is returned for other sizes.
+    pub fn facelets(&self) -> Option<[Face; 54]> {
+        if self.size != 3 {
+            return None;
+        }
+
+        let mut out = [Face::Up; 54];
+
+        for (i, &face) in FACELET_FACES.iter().enumerate() {
+            for j in 0..9 {
+                let sticker = self.stickers[face as usize][j];
+                out[i * 9 + j] = *FACELET_FACES
+                    .iter()
+                    .find(|&&f| self.stickers[f as usize][4] == sticker)?;
             }
-        } else {
-            for n in 0..3 {
-                self.swap_sides(face, surrounding[n], surrounding[n + 1]);
+        }
+
+        Some(out)
+    }
+
+    /// Returns the face each sticker's colour belongs to on a solved cube, in
+    /// the same order as `facelets`, or `None` if the cube is not a 3x3.
+    pub fn colour_facelets(&self) -> Option<[Face; 54]> {
+        if self.size != 3 {
+            return None;
+        }
+
+        let mut out = [Face::Up; 54];
+
+        for (i, &face) in FACELET_FACES.iter().enumerate() {
+            for j in 0..9 {
+                out[i * 9 + j] = self.stickers[face as usize][j].home();
             }
         }
+
+        Some(out)
     }
 
-    pub fn slice(&mut self, slice: Slice, rev: bool) {
-        let surrounding = slice.surrounding();
-        if rev {
-            for n in (0..3).rev() {
-                self.swap_slice(slice, surrounding[n], surrounding[n + 1]);
+    /// Checks that the stickers describe a cube that can be solved, naming
+    /// the facelets at fault when it can not. Cubes other than the 3x3 can
+    /// not be checked.
+    pub fn validate(&self) -> Result<(), StateError> {
+        let colours = self
+            .colour_facelets()
+            .ok_or(StateError::Unsupported(self.size))?;
+
+        for &face in &FACELET_FACES {
+            let facelets: Vec<_> = (0..54).filter(|&i| colours[i] == face).collect();
+            if facelets.len() != 9 {
+                return Err(StateError::ColourCount(face, facelets));
             }
-        } else {
-            for n in 0..3 {
-                self.swap_slice(slice, surrounding[n], surrounding[n + 1]);
+        }
+
+        for i in 0..6 {
+            for j in i + 1..6 {
+                if colours[i * 9 + 4] == colours[j * 9 + 4] {
+                    return Err(StateError::DuplicatePiece(vec![i * 9 + 4, j * 9 + 4]));
+                }
             }
         }
+
+        // every colour has a centre now so the facelets can be read
+        let facelets = self.facelets().unwrap();
+        CubieCube::check_facelets(&facelets).map(|_| ())
     }
 
-    pub fn rotate(&mut self, rot: Rotate, rev: bool) {
-        match rot {
-            Rotate::X => self.x(rev),
-            Rotate::Y => self.y(rev),
-            Rotate::Z => self.z(rev),
+    /// Reads a state in the common 54 character facelet format, such as
+    /// `UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB` for the solved
+    /// cube. Each letter names the face whose colour the sticker has.
+    pub fn from_facelet_str(s: &str) -> Result<Stickers, FaceletError> {
+        let mut facelets = [Face::Up; 54];
+        let mut len = 0;
+
+        for (i, c) in s.chars().enumerate() {
+            if i < 54 {
+                facelets[i] = Face::from_letter(c).ok_or(FaceletError::InvalidChar(i, c))?;
+            }
+            len += 1;
+        }
+        if len != 54 {
+            return Err(FaceletError::Length(len));
         }
-    }
 
-    fn x(&mut self, rev: bool) {
-        self.slice(Slice::Middle, !rev);
-        self.face(Face::Right, rev);
-        self.face(Face::Left, !rev);
+        let stickers = Stickers::from_colour_facelets(&facelets);
+        stickers.validate().map_err(FaceletError::State)?;
+        Ok(stickers)
     }
 
-    fn y(&mut self, rev: bool) {
-        self.slice(Slice::Equator, !rev);
-        self.face(Face::Up, rev);
-        self.face(Face::Down, !rev);
+    /// Writes the state in the format read by `from_facelet_str`. Letters
+    /// name the face whose centre currently has the sticker's colour, so
+    /// rotations and slice moves are written relative to the centres.
+    pub fn to_facelet_str(&self) -> Result<String, FaceletError> {
+        let facelets = self
+            .facelets()
+            .ok_or(FaceletError::Unsupported(self.size))?;
+        Ok(facelets.iter().map(|f| f.letter()).collect())
     }
 
-    fn z(&mut self, rev: bool) {
-        self.slice(Slice::Standing, rev);
-        self.face(Face::Front, rev);
-        self.face(Face::Back, !rev);
-    }
+    pub fn from_colour_facelets(facelets: &[Face; 54]) -> Stickers {
+        let mut stickers = Stickers::new();
 
-    fn swap_pieces<'a>(
-        &mut self,
-        mut f1: Face,
-        mut i1: &'a [usize],
-        mut f2: Face,
-        mut i2: &'a [usize],
-    ) {
-        if f1 as usize > f2 as usize {
-            swap(&mut f1, &mut f2);
-            swap(&mut i1, &mut i2);
+        for (i, &face) in FACELET_FACES.iter().enumerate() {
+            for j in 0..9 {
+                stickers.stickers[face as usize][j] = Sticker::from_home(facelets[i * 9 + j]);
+            }
         }
 
-        let (l, r) = self.stickers.split_at_mut(f2 as usize);
-        let (l, r) = (&mut l[f1 as usize], &mut r[0]);
-
-        for (&i, &j) in i1.iter().zip(i2) {
-            swap(&mut l[i], &mut r[j]);
-        }
+        stickers
     }
 
-    fn swap_slice(&mut self, slice: Slice, f1: Face, f2: Face) {
-        let i1 = slice.intersect(f1);
-        let i2 = slice.intersect(f2);
-        self.swap_pieces(f1, &i1, f2, &i2);
+    /// Turns the given layers a quarter turn clockwise as seen from `face`,
+    /// counting the face itself as layer 0. Layers past the far side of the
+    /// cube are ignored.
+    pub fn turn(&mut self, face: Face, layers: Range<usize>, rev: bool) {
+        let n = self.size;
+        let old = self.stickers.clone();
+
+        for &from in &FACELET_FACES {
+            for (i, &sticker) in old[from as usize].iter().enumerate() {
+                let p = grid::sticker_point(n, from, i);
+                if !layers.contains(&grid::depth(n, face, p)) {
+                    continue;
+                }
+
+                let to = grid::face_from_normal(grid::rotate(face, rev, grid::normal(from)));
+                let j = grid::sticker_index(n, to, grid::rotate(face, rev, p));
+                self.stickers[to as usize][j] = sticker;
+            }
+        }
     }
 
-    fn swap_faces(&mut self, f1: Face, f2: Face) {
-        self.stickers.swap(f1 as usize, f2 as usize);
+    pub fn face(&mut self, face: Face, rev: bool) {
+        self.turn(face, 0..1, rev);
     }
 
-    fn swap_sides(&mut self, fface: Face, f1: Face, f2: Face) {
-        let i1 = fface.intersect(f1) as usize;
-        let i2 = fface.intersect(f2) as usize;
-        self.swap_pieces(
-            f1,
-            &[i1, (i1 + 1) % 8, (i1 + 2) % 8],
-            f2,
-            &[i2, (i2 + 1) % 8, (i2 + 2) % 8],
-        );
+    /// Turns every inner layer, which is the single middle layer on odd
+    /// cubes.
+    pub fn slice(&mut self, slice: Slice, rev: bool) {
+        let face = match slice {
+            Slice::Middle => Face::Left,
+            Slice::Equator => Face::Down,
+            Slice::Standing => Face::Front,
+        };
+        self.turn(face, 1..self.size - 1, rev);
     }
 
-    fn rotate_face(&mut self, face: Face, rev: bool) {
-        let face = &mut self.stickers[face as usize];
-        if rev {
-            for n in 0..7 {
-                face.swap(n, n + 2);
-            }
-        } else {
-            for n in (0..7).rev() {
-                face.swap(n, n + 2);
-            }
-        }
+    pub fn rotate(&mut self, rot: Rotate, rev: bool) {
+        let face = match rot {
+            Rotate::X => Face::Right,
+            Rotate::Y => Face::Up,
+            Rotate::Z => Face::Front,
+        };
+        self.turn(face, 0..self.size, rev);
     }
 }
 
@@ -328,6 +393,101 @@ impl Stickers {
 mod tests {
     use super::*;
 
+    #[test]
+    fn facelet_str() {
+        let solved = "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB";
+        assert_eq!(Stickers::new().to_facelet_str().unwrap(), solved);
+        assert_eq!(Stickers::from_facelet_str(solved), Ok(Stickers::new()));
+
+        // the superflip, with every edge flipped in place
+        let superflip = "UBULURUFURURFRBRDRFUFLFRFDFDFDLDRDBDLULBLFLDLBUBRBLBDB";
+        let stickers = Stickers::from_facelet_str(superflip).unwrap();
+        assert_eq!(stickers.to_facelet_str().unwrap(), superflip);
+
+        let mut turned = Stickers::new();
+        let alg: crate::components::algorithm::Algorithm = "R".parse().unwrap();
+        alg.apply_stickers(&mut turned);
+        let s = turned.to_facelet_str().unwrap();
+        assert_eq!(&s[..9], "UUFUUFUUF");
+        assert_eq!(&s[18..27], "FFDFFDFFD");
+        assert_eq!(Stickers::from_facelet_str(&s), Ok(turned));
+
+        // rotations and slice moves turn the centres, which the letters
+        // follow
+        for alg in &["R U x", "F M"] {
+            let mut stickers = Stickers::new();
+            let alg: crate::components::algorithm::Algorithm = alg.parse().unwrap();
+            alg.apply_stickers(&mut stickers);
+            let s = stickers.to_facelet_str().unwrap();
+            let centres: String = s.chars().skip(4).step_by(9).collect();
+            assert_eq!(centres, "URFDLB");
+            let round_trip = Stickers::from_facelet_str(&s).unwrap().to_facelet_str();
+            assert_eq!(round_trip, Ok(s));
+        }
+
+        assert_eq!(
+            Stickers::from_facelet_str(&solved[1..]),
+            Err(FaceletError::Length(53))
+        );
+        assert_eq!(
+            Stickers::from_facelet_str(&solved.replace("DDD", "DXD")),
+            Err(FaceletError::InvalidChar(28, 'X'))
+        );
+        // U2 and B2 swapped flips a single edge
+        let mut flipped: Vec<char> = solved.chars().collect();
+        flipped.swap(1, 46);
+        assert_eq!(
+            Stickers::from_facelet_str(&flipped.into_iter().collect::<String>()),
+            Err(FaceletError::State(StateError::FlippedEdge(vec![1, 46])))
+        );
+
+        assert_eq!(
+            Stickers::with_size(4).to_facelet_str(),
+            Err(FaceletError::Unsupported(4))
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let solved = Stickers::new().colour_facelets().unwrap();
+        let check = |swaps: &[(usize, usize)]| {
+            let mut f = solved;
+            for &(a, b) in swaps {
+                f.swap(a, b);
+            }
+            Stickers::from_colour_facelets(&f).validate()
+        };
+
+        assert_eq!(check(&[]), Ok(()));
+        // U9 R1 F3 twisted in place
+        assert_eq!(
+            check(&[(8, 9), (8, 20)]),
+            Err(StateError::TwistedCorner(vec![8, 9, 20]))
+        );
+        // U6 R2 flipped
+        assert_eq!(check(&[(5, 10)]), Err(StateError::FlippedEdge(vec![5, 10])));
+        // UR and UF swapped
+        assert_eq!(
+            check(&[(5, 7), (10, 19)]),
+            Err(StateError::Parity(vec![5, 10, 7, 19]))
+        );
+        // a yellow sticker replaced by a white one
+        let mut f = solved;
+        f[0] = Face::Down;
+        let err = Stickers::from_colour_facelets(&f).validate().unwrap_err();
+        assert!(matches!(err, StateError::ColourCount(Face::Up, ref v) if v.len() == 8));
+        // U1 and F1 swapped, leaving a corner with two yellow stickers
+        assert!(matches!(
+            check(&[(0, 18)]),
+            Err(StateError::ImpossiblePiece(_))
+        ));
+
+        assert_eq!(
+            Stickers::with_size(4).validate(),
+            Err(StateError::Unsupported(4))
+        );
+    }
+
     #[test]
     fn print() {
         let mut l = Stickers::new();
@@ -367,4 +527,38 @@ mod tests {
         println!("{}", l.solved());
         println!("{:?}", l);
     }
+
+    #[test]
+    fn face_turns_keep_centres() {
+        let mut l = Stickers::new();
+        l.face(Face::Front, false);
+        l.face(Face::Right, false);
+
+        let solved = Stickers::new();
+        for (face, solved) in l.stickers.iter().zip(&solved.stickers) {
+            assert_eq!(face[4], solved[4]);
+        }
+
+        l.face(Face::Right, true);
+        l.face(Face::Front, true);
+        assert!(l.solved());
+    }
+
+    #[test]
+    fn middle_slice_matches_turned_equator() {
+        let mut m = Stickers::new();
+        m.face(Face::Front, false);
+        m.face(Face::Right, false);
+        m.slice(Slice::Middle, false);
+
+        // seen from the left after a z rotation, E' turns the M slice
+        let mut e = Stickers::new();
+        e.face(Face::Front, false);
+        e.face(Face::Right, false);
+        e.rotate(Rotate::Z, false);
+        e.slice(Slice::Equator, true);
+        e.rotate(Rotate::Z, true);
+
+        assert!(m.stickers == e.stickers);
+    }
 }
@@ -70,26 +70,29 @@ impl Transform {
         self.rot = rot.into()
     }
 
+    /// Turns to face `target` with world up, which is -y, at the top.
     pub fn look_at(&mut self, target: Vector3<f32>) {
-        let dir = (target - self.pos.coords).normalize();
-        //let mut up = -self.up().abs();
-        let mut up = Vector3::<f32>::y();
-
-        if (dir.abs() - up).abs().sum() < 0.001 {
-            //up = self.forward();
-            up = Vector3::z();
-        }
-
-        self.rot = UnitQuaternion::face_towards(&dir, &up);
+        self.look_at_up(target, -Vector3::y());
     }
 
-    pub fn look_at2(&mut self, _p: Vector3<f32>) {
-        if let Some(rot) = UnitQuaternion::rotation_between(&self.forward(), &self.pos.coords) {
-            self.rot = rot * self.rot;
-        }
-        if let Some(rot) = UnitQuaternion::rotation_between(&-self.forward(), &self.pos.coords) {
-            self.rot = rot * self.rot;
-        }
+    /// Turns to face `target` keeping `up` as the top of the view. When
+    /// `target` is straight above or below, the current up direction is kept
+    /// instead so the view does not flip.
+    pub fn look_at_up(&mut self, target: Vector3<f32>, up: Vector3<f32>) {
+        let dir = match (target - self.pos.coords).try_normalize(1.0e-6) {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        let up = [up, self.up(), -self.forward()]
+            .iter()
+            .find(|up| dir.cross(up).norm() > 1.0e-3)
+            .cloned()
+            .unwrap();
+
+        // `up` points along the local -y axis while `face_towards` aligns its
+        // second argument with +y
+        self.rot = UnitQuaternion::face_towards(&dir, &-up);
     }
 
     pub fn view(&self) -> Matrix4<f32> {
@@ -105,3 +108,40 @@ impl Transform {
             * model.append_nonuniform_scaling(&self.scale)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vector3<f32>, b: Vector3<f32>) {
+        assert!((a - b).norm() < 1.0e-5, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn look_at() {
+        let mut transform = Transform {
+            pos: Point3::new(0.0, 0.0, -14.0),
+            ..Transform::default()
+        };
+        transform.look_at(Vector3::zeros());
+        assert_close(transform.forward(), Vector3::z());
+        assert_close(transform.up(), -Vector3::y());
+        assert_close(transform.right(), Vector3::x());
+
+        transform.pos = Point3::new(10.0, -10.0, 0.0);
+        transform.look_at(Vector3::zeros());
+        assert_close(
+            transform.forward(),
+            Vector3::new(-1.0, 1.0, 0.0).normalize(),
+        );
+        assert!(transform.up().y < 0.0);
+        assert!(transform.right().dot(&transform.forward()).abs() < 1.0e-5);
+
+        // looking straight down keeps the previous up direction
+        let up = transform.up();
+        transform.pos = Point3::new(0.0, -10.0, 0.0);
+        transform.look_at(Vector3::zeros());
+        assert_close(transform.forward(), Vector3::y());
+        assert!(transform.up().dot(&up) > 0.0);
+    }
+}
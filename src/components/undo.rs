@@ -0,0 +1,80 @@
+use crate::components::algorithm::Move;
+
+/// The moves that can be taken back and made again. Each entry is a whole
+/// move, so a double turn or a turn taking the inner layers with it is
+/// undone in one go.
+#[derive(Debug, Clone, Default)]
+pub struct UndoStack {
+    done: Vec<Move>,
+    undone: Vec<Move>,
+}
+
+impl UndoStack {
+    pub fn new() -> UndoStack {
+        UndoStack::default()
+    }
+
+    /// Records a new move. Anything that was undone can no longer be redone.
+    pub fn push(&mut self, mv: Move) {
+        self.done.push(mv);
+        self.undone.clear();
+    }
+
+    /// Takes back the last move, returning the move that reverses it.
+    pub fn undo(&mut self) -> Option<Move> {
+        let mv = self.done.pop()?;
+        self.undone.push(mv);
+        Some(mv.inverse())
+    }
+
+    /// Makes the last undone move again.
+    pub fn redo(&mut self) -> Option<Move> {
+        let mv = self.undone.pop()?;
+        self.done.push(mv);
+        Some(mv)
+    }
+
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::algorithm::{Algorithm, Turn};
+    use crate::components::stickers::Stickers;
+
+    #[test]
+    fn undo_redo() {
+        let mut stack = UndoStack::new();
+        let mut stickers = Stickers::with_size(4);
+        let alg: Algorithm = "R U2 3Rw2 F".parse().unwrap();
+        for mv in alg.moves() {
+            mv.apply_stickers(&mut stickers);
+            stack.push(mv);
+        }
+        let scrambled = format!("{:?}", stickers);
+
+        for _ in 0..4 {
+            stack.undo().unwrap().apply_stickers(&mut stickers);
+        }
+        assert!(stickers.solved());
+        assert_eq!(stack.undo(), None);
+
+        while let Some(mv) = stack.redo() {
+            mv.apply_stickers(&mut stickers);
+        }
+        assert_eq!(format!("{:?}", stickers), scrambled);
+
+        // the double turn with its inner layers comes back as one move
+        stack.undo().unwrap();
+        let mv = stack.undo().unwrap();
+        assert!(mv.double && mv.with && mv.rev);
+        assert_eq!(mv.turn, Turn::Right);
+
+        stack.push(Move::new(Turn::Up, false, false, false));
+        assert_eq!(stack.redo(), None);
+    }
+}
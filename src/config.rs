@@ -0,0 +1,535 @@
+//! Settings read from `config.toml` in the user's config directory. Every
+//! setting is optional and anything that cannot be used is reported and left
+//! at its default, so a mistake never stops the app from starting.
+
+use crate::components::algorithm::Move;
+use crate::components::easing::Easing;
+use crate::components::scheme::{self, Colour, ColourScheme};
+use crate::error::{self, Error};
+use crate::util;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use toml::value::{Table, Value};
+
+const CONFIG_FILE: &str = "config.toml";
+const MAX_WINDOW_SIZE: i64 = 16384;
+const MAX_TURN_DURATION: i64 = 10_000;
+
+// the keys along the top row and the letters after them, as named by SDL
+const DEFAULT_KEYS: [(&str, &str); 12] = [
+    ("1", "L"),
+    ("2", "R"),
+    ("3", "U"),
+    ("4", "D"),
+    ("5", "F"),
+    ("6", "B"),
+    ("7", "x"),
+    ("8", "y"),
+    ("9", "z"),
+    ("I", "M"),
+    ("O", "E"),
+    ("P", "S"),
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Modifier {
+    Shift,
+    Ctrl,
+    Alt,
+}
+
+impl Modifier {
+    fn from_name(name: &str) -> Option<Modifier> {
+        match name {
+            "shift" => Some(Modifier::Shift),
+            "ctrl" => Some(Modifier::Ctrl),
+            "alt" => Some(Modifier::Alt),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Modifier::Shift => "shift",
+            Modifier::Ctrl => "ctrl",
+            Modifier::Alt => "alt",
+        }
+    }
+}
+
+/// Which modifier held with a move key changes the move in each way.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Modifiers {
+    pub reverse: Modifier,
+    pub double: Modifier,
+    /// Takes the inner layers along with a face turn.
+    pub with: Modifier,
+}
+
+impl Default for Modifiers {
+    fn default() -> Modifiers {
+        Modifiers {
+            reverse: Modifier::Shift,
+            double: Modifier::Ctrl,
+            with: Modifier::Alt,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub width: u32,
+    pub height: u32,
+    pub vsync: bool,
+    pub clear_colour: Colour,
+    /// How long a quarter turn takes to animate.
+    pub turn_duration: Duration,
+    pub easing: Easing,
+    pub scheme: ColourScheme,
+    /// Schemes defined in the file, which can be switched to as well as the
+    /// built in ones.
+    pub schemes: Vec<ColourScheme>,
+    /// Keys as named by SDL and the move each makes.
+    pub keys: Vec<(String, Move)>,
+    pub modifiers: Modifiers,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        let keys = DEFAULT_KEYS
+            .iter()
+            .map(|&(key, mv)| (key.to_string(), mv.parse().unwrap()))
+            .collect();
+
+        Config {
+            width: 1600,
+            height: 900,
+            vsync: true,
+            clear_colour: [0.5, 0.0, 0.5],
+            turn_duration: Duration::from_millis(150),
+            easing: Easing::default(),
+            scheme: ColourScheme::default(),
+            schemes: Vec::new(),
+            keys,
+            modifiers: Modifiers::default(),
+        }
+    }
+}
+
+/// A setting that could not be used. These are reported but, unlike an
+/// `Error`, do not stop the config from loading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Where the setting is, such as `window.width`. Empty when the whole
+    /// file could not be read.
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.key.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.key, self.message)
+        }
+    }
+}
+
+// goes through the settings, noting each one that is not usable
+struct Reader {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Reader {
+    fn warn<M: fmt::Display>(&mut self, key: &str, message: M) {
+        self.diagnostics.push(Diagnostic {
+            key: key.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    fn table<'a>(&mut self, key: &str, value: &'a Value) -> Option<&'a Table> {
+        match value {
+            Value::Table(table) => Some(table),
+            _ => self.expected(key, "a table", value),
+        }
+    }
+
+    fn str<'a>(&mut self, key: &str, value: &'a Value) -> Option<&'a str> {
+        match value {
+            Value::String(s) => Some(s),
+            _ => self.expected(key, "a string", value),
+        }
+    }
+
+    fn bool(&mut self, key: &str, value: &Value) -> Option<bool> {
+        match value {
+            Value::Boolean(b) => Some(*b),
+            _ => self.expected(key, "true or false", value),
+        }
+    }
+
+    fn int(&mut self, key: &str, value: &Value, min: i64, max: i64) -> Option<i64> {
+        match value {
+            Value::Integer(i) if (min..=max).contains(i) => Some(*i),
+            Value::Integer(i) => {
+                self.warn(key, format!("{} is not between {} and {}", i, min, max));
+                None
+            }
+            _ => self.expected(key, "a whole number", value),
+        }
+    }
+
+    fn expected<T>(&mut self, key: &str, what: &str, value: &Value) -> Option<T> {
+        self.warn(
+            key,
+            format!("expected {}, found {}", what, value.type_str()),
+        );
+        None
+    }
+}
+
+impl Config {
+    /// The path the config is loaded from.
+    pub fn path() -> Option<PathBuf> {
+        util::config_dir().map(|dir| dir.join(CONFIG_FILE))
+    }
+
+    /// Loads the config from the user's config directory. A missing file
+    /// gives the defaults.
+    pub fn load() -> error::Result<(Config, Vec<Diagnostic>)> {
+        match Config::path() {
+            Some(path) => Config::load_from(&path),
+            None => Ok((Config::default(), Vec::new())),
+        }
+    }
+
+    /// Loads the config from `path`, failing only if the file is there but
+    /// cannot be read.
+    pub fn load_from(path: &Path) -> error::Result<(Config, Vec<Diagnostic>)> {
+        match fs::read_to_string(path) {
+            Ok(s) => Ok(Config::parse(&s)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok((Config::default(), Vec::new())),
+            Err(error) => Err(Error::Config {
+                path: path.to_path_buf(),
+                error,
+            }),
+        }
+    }
+
+    /// Reads a config, returning the settings that could be used and a
+    /// diagnostic for each that could not.
+    pub fn parse(s: &str) -> (Config, Vec<Diagnostic>) {
+        let mut config = Config::default();
+        let mut reader = Reader {
+            diagnostics: Vec::new(),
+        };
+
+        match s.parse::<Value>() {
+            Ok(Value::Table(root)) => config.read(&mut reader, &root),
+            Ok(_) => reader.warn("", "expected a table"),
+            Err(e) => reader.warn("", e),
+        }
+        (config, reader.diagnostics)
+    }
+
+    fn read(&mut self, reader: &mut Reader, root: &Table) {
+        let mut scheme = None;
+        for (section, value) in root {
+            let table = match reader.table(section, value) {
+                Some(table) => table,
+                None => continue,
+            };
+            match section.as_str() {
+                "window" => self.read_window(reader, table),
+                "animation" => self.read_animation(reader, table),
+                "colours" => scheme = self.read_colours(reader, table),
+                "keys" => self.read_keys(reader, table),
+                "modifiers" => self.read_modifiers(reader, table),
+                _ => reader.warn(section, "unknown section"),
+            }
+        }
+
+        // schemes are chosen once all of them have been read as the file
+        // may name one of its own
+        if let Some(name) = scheme {
+            let found = self.schemes.iter().find(|s| s.name == name).cloned();
+            match found.or_else(|| ColourScheme::by_name(&name)) {
+                Some(scheme) => self.scheme = scheme,
+                None => match ColourScheme::parse("custom", &name) {
+                    Ok(scheme) => self.scheme = scheme,
+                    Err(_) => reader.warn("colours.scheme", format!("unknown scheme {:?}", name)),
+                },
+            }
+        }
+    }
+
+    fn read_window(&mut self, reader: &mut Reader, table: &Table) {
+        for (key, value) in table {
+            let path = format!("window.{}", key);
+            match key.as_str() {
+                "width" => {
+                    if let Some(i) = reader.int(&path, value, 1, MAX_WINDOW_SIZE) {
+                        self.width = i as u32;
+                    }
+                }
+                "height" => {
+                    if let Some(i) = reader.int(&path, value, 1, MAX_WINDOW_SIZE) {
+                        self.height = i as u32;
+                    }
+                }
+                "vsync" => {
+                    if let Some(b) = reader.bool(&path, value) {
+                        self.vsync = b;
+                    }
+                }
+                "clear_colour" => {
+                    if let Some(s) = reader.str(&path, value) {
+                        match scheme::parse_colour(s) {
+                            Ok(colour) => self.clear_colour = colour,
+                            Err(e) => reader.warn(&path, e),
+                        }
+                    }
+                }
+                _ => reader.warn(&path, "unknown setting"),
+            }
+        }
+    }
+
+    fn read_animation(&mut self, reader: &mut Reader, table: &Table) {
+        for (key, value) in table {
+            let path = format!("animation.{}", key);
+            match key.as_str() {
+                "turn_duration" => {
+                    if let Some(ms) = reader.int(&path, value, 0, MAX_TURN_DURATION) {
+                        self.turn_duration = Duration::from_millis(ms as u64);
+                    }
+                }
+                "easing" => {
+                    if let Some(s) = reader.str(&path, value) {
+                        match Easing::from_name(s) {
+                            Some(easing) => self.easing = easing,
+                            None => reader.warn(&path, format!("unknown easing {:?}", s)),
+                        }
+                    }
+                }
+                _ => reader.warn(&path, "unknown setting"),
+            }
+        }
+    }
+
+    // returns the name of the scheme to use
+    fn read_colours(&mut self, reader: &mut Reader, table: &Table) -> Option<String> {
+        let mut scheme = None;
+        for (key, value) in table {
+            let path = format!("colours.{}", key);
+            match key.as_str() {
+                "scheme" => scheme = reader.str(&path, value).map(str::to_string),
+                "schemes" => {
+                    let schemes = match reader.table(&path, value) {
+                        Some(schemes) => schemes,
+                        None => continue,
+                    };
+                    for (name, spec) in schemes {
+                        let path = format!("{}.{}", path, name);
+                        if let Some(spec) = reader.str(&path, spec) {
+                            match ColourScheme::parse(name.as_str(), spec) {
+                                Ok(scheme) => self.schemes.push(scheme),
+                                Err(e) => reader.warn(&path, e),
+                            }
+                        }
+                    }
+                }
+                _ => reader.warn(&path, "unknown setting"),
+            }
+        }
+        scheme
+    }
+
+    fn read_keys(&mut self, reader: &mut Reader, table: &Table) {
+        for (key, value) in table {
+            let path = format!("keys.{:?}", key);
+            let mv = match reader.str(&path, value) {
+                Some(s) => s,
+                None => continue,
+            };
+            match mv.parse::<Move>() {
+                Ok(mv) => {
+                    self.keys.retain(|(k, _)| k != key);
+                    self.keys.push((key.clone(), mv));
+                }
+                Err(e) => reader.warn(&path, format!("invalid move {:?}: {}", mv, e)),
+            }
+        }
+    }
+
+    fn read_modifiers(&mut self, reader: &mut Reader, table: &Table) {
+        let mut modifiers = self.modifiers;
+        for (key, value) in table {
+            let path = format!("modifiers.{}", key);
+            let modifier = match key.as_str() {
+                "reverse" => &mut modifiers.reverse,
+                "double" => &mut modifiers.double,
+                "with" => &mut modifiers.with,
+                _ => {
+                    reader.warn(&path, "unknown setting");
+                    continue;
+                }
+            };
+            if let Some(name) = reader.str(&path, value) {
+                match Modifier::from_name(name) {
+                    Some(m) => *modifier = m,
+                    None => reader.warn(
+                        &path,
+                        format!("unknown modifier {:?}, expected shift, ctrl or alt", name),
+                    ),
+                }
+            }
+        }
+
+        // each modifier can only change a move in one way, so the whole
+        // table is ignored if two of them are the same
+        let roles = [
+            ("reverse", modifiers.reverse),
+            ("double", modifiers.double),
+            ("with", modifiers.with),
+        ];
+        let mut clash = false;
+        for (i, &(role, modifier)) in roles.iter().enumerate() {
+            if let Some(&(other, _)) = roles[..i].iter().find(|&&(_, m)| m == modifier) {
+                // blame the role that was set in the file
+                let (role, other) = if table.contains_key(role) {
+                    (role, other)
+                } else {
+                    (other, role)
+                };
+                reader.warn(
+                    &format!("modifiers.{}", role),
+                    format!("{} is also used for {}", modifier.name(), other),
+                );
+                clash = true;
+            }
+        }
+        if !clash {
+            self.modifiers = modifiers;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::algorithm::Turn;
+
+    #[test]
+    fn parse() {
+        let (config, diagnostics) = Config::parse(
+            r##"
+[window]
+width = 1024
+vsync = false
+clear_colour = "#000000"
+
+[animation]
+turn_duration = 80
+easing = "overshoot"
+
+[colours]
+scheme = "mine"
+schemes.mine = "U=#ffffff,D=#ffd500,L=#ff5800,R=#b71234,F=#009b48,B=#0046ad"
+
+[keys]
+J = "U"
+"1" = "Lw'"
+
+[modifiers]
+double = "alt"
+with = "ctrl"
+"##,
+        );
+
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert_eq!((config.width, config.height), (1024, 900));
+        assert!(!config.vsync);
+        assert_eq!(config.clear_colour, [0.0; 3]);
+        assert_eq!(config.turn_duration, Duration::from_millis(80));
+        assert_eq!(config.easing, Easing::Overshoot);
+        assert_eq!(config.scheme.name, "mine");
+        assert_eq!(config.schemes.len(), 1);
+        assert_eq!(config.modifiers.double, Modifier::Alt);
+
+        let key = |name: &str| config.keys.iter().find(|(k, _)| k == name).map(|k| k.1);
+        assert_eq!(key("J"), Some(Move::new(Turn::Up, false, false, false)));
+        assert_eq!(key("1"), Some(Move::new(Turn::Left, true, false, true)));
+        assert_eq!(key("2"), Some(Move::new(Turn::Right, false, false, false)));
+    }
+
+    #[test]
+    fn diagnostics() {
+        let (config, diagnostics) = Config::parse(
+            r#"
+[window]
+width = 0
+height = "tall"
+title = "cube"
+
+[animation]
+easing = "bouncy"
+
+[colours]
+scheme = "neon"
+
+[keys]
+Q = "Q"
+
+[modifiers]
+reverse = "meta"
+
+[sound]
+"#,
+        );
+
+        let keys: Vec<_> = diagnostics.iter().map(|d| d.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "animation.easing",
+                "keys.\"Q\"",
+                "modifiers.reverse",
+                "sound",
+                "window.height",
+                "window.title",
+                "window.width",
+                "colours.scheme",
+            ]
+        );
+        assert_eq!(
+            diagnostics[4].to_string(),
+            "window.height: expected a whole number, found string"
+        );
+        assert_eq!(
+            diagnostics[6].to_string(),
+            "window.width: 0 is not between 1 and 16384"
+        );
+        assert_eq!(config, Config::default());
+
+        let (config, diagnostics) = Config::parse("[modifiers]\ndouble = \"shift\"");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "modifiers.double: shift is also used for reverse"
+        );
+        assert_eq!(config.modifiers, Modifiers::default());
+
+        let (config, diagnostics) = Config::parse("[window\nwidth = 5");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].key.is_empty());
+        assert!(diagnostics[0].message.contains("line 1"));
+        assert_eq!(config, Config::default());
+    }
+}
@@ -1,6 +1,169 @@
+use crate::components::algorithm::DepthError;
+use crate::timer::cstimer::CsTimerError;
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
-    ShaderCompileFail(String),
+    /// SDL could not start or could not open a window.
+    Sdl(String),
+    /// No OpenGL context could be made for the window.
+    Context(String),
+    ShaderCompile {
+        /// Which shader failed, such as the file it was loaded from.
+        name: String,
+        log: String,
+        source: String,
+    },
+    ShaderLink(String),
+    Framebuffer(String),
+    Model {
+        path: PathBuf,
+        error: tobj::LoadError,
+    },
+    Texture {
+        path: PathBuf,
+        error: image::ImageError,
+    },
+    Manifest {
+        path: PathBuf,
+        error: toml::de::Error,
+    },
+    /// An ID that is not in the asset manifest.
+    UnknownAsset {
+        kind: &'static str,
+        id: String,
+    },
+    /// A move reaches past the last layer of the cube.
+    Depth(DepthError),
+    /// The config file exists but could not be read.
+    Config {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// The solve history could not be loaded.
+    History(io::Error),
+    CsTimer {
+        path: PathBuf,
+        error: CsTimerError,
+    },
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Sdl(e) => write!(f, "SDL error: {}", e),
+            Error::Context(e) => write!(f, "failed to create OpenGL context: {}", e),
+            Error::ShaderCompile { name, log, source } => {
+                write!(f, "failed to compile {}:", name)?;
+                annotate_log(f, log, source)
+            }
+            Error::ShaderLink(log) => write!(f, "failed to link shaders: {}", log.trim_end()),
+            Error::Framebuffer(e) => write!(f, "failed to create framebuffer: {}", e),
+            Error::Model { path, error } => {
+                write!(f, "failed to load model {}: {}", path.display(), error)
+            }
+            Error::Texture { path, error } => {
+                write!(f, "failed to load texture {}: {}", path.display(), error)
+            }
+            Error::Manifest { path, error } => {
+                write!(f, "invalid asset manifest {}: {}", path.display(), error)
+            }
+            Error::UnknownAsset { kind, id } => {
+                write!(f, "no {} with the ID {:?} in the asset manifest", kind, id)
+            }
+            Error::Depth(e) => e.fmt(f),
+            Error::Config { path, error } => {
+                write!(f, "failed to read config {}: {}", path.display(), error)
+            }
+            Error::History(e) => write!(f, "failed to load solve history: {}", e),
+            Error::CsTimer { path, error } => write!(f, "{}: {}", path.display(), error),
+            Error::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Model { error, .. } => Some(error),
+            Error::Texture { error, .. } => Some(error),
+            Error::Manifest { error, .. } => Some(error),
+            Error::Depth(error) => Some(error),
+            Error::CsTimer { error, .. } => Some(error),
+            Error::Config { error, .. } | Error::Io { error, .. } | Error::History(error) => {
+                Some(error)
+            }
+            _ => None,
+        }
+    }
+}
+
+// writes each line of a compiler log followed by the line of source it
+// refers to
+fn annotate_log(f: &mut fmt::Formatter, log: &str, source: &str) -> fmt::Result {
+    let lines: Vec<_> = source.lines().collect();
+    for entry in log.lines().filter(|l| !l.trim().is_empty()) {
+        write!(f, "\n{}", entry)?;
+        if let Some(line) = log_line(entry).filter(|&n| n > 0 && n <= lines.len()) {
+            write!(f, "\n    {} | {}", line, lines[line - 1].trim_end())?;
+        }
+    }
+    Ok(())
+}
+
+// the source line a log entry is about. Drivers write the source string and
+// line as `0:12(5): error` (Mesa), `0(12) : error` (Nvidia) or
+// `ERROR: 0:12: ...` (most others)
+fn log_line(entry: &str) -> Option<usize> {
+    let entry = entry.trim_start();
+    let entry = ["ERROR: ", "WARNING: "]
+        .iter()
+        .find_map(|prefix| entry.strip_prefix(prefix))
+        .unwrap_or(entry);
+
+    let rest = entry.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() == entry.len() {
+        return None;
+    }
+    let rest = rest.strip_prefix(':').or_else(|| rest.strip_prefix('('))?;
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_lines() {
+        assert_eq!(log_line("0:12(5): error: `foo' undeclared"), Some(12));
+        assert_eq!(log_line("0(7) : error C0000: syntax error"), Some(7));
+        assert_eq!(log_line("ERROR: 0:3: 'x' : undeclared identifier"), Some(3));
+        assert_eq!(log_line("ERROR: 1 compilation errors."), None);
+        assert_eq!(log_line("link failed"), None);
+
+        let error = Error::ShaderCompile {
+            name: "shader.fs".to_string(),
+            log: "0:2(5): error: syntax error\n0:9(1): error: past the end\n".to_string(),
+            source: "#version 320 es\nvoid main() {\n}\n".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "failed to compile shader.fs:\n\
+             0:2(5): error: syntax error\n    \
+             2 | void main() {\n\
+             0:9(1): error: past the end"
+        );
+    }
 }
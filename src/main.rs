@@ -1,21 +1,256 @@
 #[allow(dead_code)]
 mod components;
 #[allow(dead_code)]
+mod config;
+#[allow(dead_code)]
 mod error;
 #[allow(dead_code)]
 mod ogl;
 #[allow(dead_code)]
+mod solver;
+#[allow(dead_code)]
+mod timer;
+#[allow(dead_code)]
 mod util;
 #[allow(dead_code)]
 mod world_object;
 
+use crate::components::algorithm::Algorithm;
+use crate::components::scheme::ColourScheme;
+use crate::components::stickers::{MAX_SIZE, MIN_SIZE};
+use crate::config::Config;
+use crate::error::Error;
+use crate::ogl::manifest;
 use crate::ogl::render::Renderer;
-use crate::ogl::resources::ResourceManager;
+use crate::timer::cstimer;
+use crate::timer::history::History;
 use crate::world_object::cube::Cube;
 
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "usage: cube [size] [--scheme SCHEME] [--watch-shaders]
+       cube [size] [--scheme SCHEME] --png FILE [--alg ALG] [--width PX]
+                   [--height PX] [--yaw DEG] [--pitch DEG]
+       cube --stats
+       cube --export-csv FILE
+       cube --import-cstimer FILE
+       cube --export-cstimer FILE
+
+SCHEME is western, japanese, high-contrast, colour-blind or the colour of
+each face such as U=#ffffff,D=#ffd500,L=#ff5800,R=#b71234,F=#009b48,B=#0046ad
+
+--watch-shaders loads the shaders from the shaders directory of the assets
+and reloads them whenever they are saved. Assets are found next to the
+executable or in $CUBE_ASSETS.
+
+Settings are read from $XDG_CONFIG_HOME/cube/config.toml, which has the
+sections [window], [animation], [colours], [keys] and [modifiers].";
+
+// distance of the camera from the cube in the default view
+const CAMERA_DISTANCE: f32 = 14.0;
+
+struct Options {
+    size: usize,
+    scheme: Option<ColourScheme>,
+    png: Option<String>,
+    watch_shaders: bool,
+    stats: bool,
+    export_csv: Option<String>,
+    import_cstimer: Option<String>,
+    export_cstimer: Option<String>,
+    alg: Algorithm,
+    width: u32,
+    height: u32,
+    yaw: f32,
+    pitch: f32,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        size: 3,
+        scheme: None,
+        png: None,
+        watch_shaders: false,
+        stats: false,
+        export_csv: None,
+        import_cstimer: None,
+        export_cstimer: None,
+        alg: Algorithm::new(),
+        width: 800,
+        height: 800,
+        yaw: 30.0,
+        pitch: 30.0,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--png" => options.png = Some(value()?),
+            "--scheme" => options.scheme = Some(parse_scheme(&value()?)?),
+            "--watch-shaders" => options.watch_shaders = true,
+            "--stats" => options.stats = true,
+            "--export-csv" => options.export_csv = Some(value()?),
+            "--import-cstimer" => options.import_cstimer = Some(value()?),
+            "--export-cstimer" => options.export_cstimer = Some(value()?),
+            "--alg" => options.alg = value()?.parse().map_err(|e| format!("--alg: {}", e))?,
+            "--width" => options.width = value()?.parse().map_err(|_| "invalid --width")?,
+            "--height" => options.height = value()?.parse().map_err(|_| "invalid --height")?,
+            "--yaw" => options.yaw = value()?.parse().map_err(|_| "invalid --yaw")?,
+            "--pitch" => options.pitch = value()?.parse().map_err(|_| "invalid --pitch")?,
+            _ => match arg.parse() {
+                Ok(size) if (MIN_SIZE..=MAX_SIZE).contains(&size) => options.size = size,
+                _ => {
+                    return Err(format!(
+                        "invalid size {}, must be {} to {}",
+                        arg, MIN_SIZE, MAX_SIZE
+                    ))
+                }
+            },
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_scheme(s: &str) -> Result<ColourScheme, String> {
+    match ColourScheme::by_name(s) {
+        Some(scheme) => Ok(scheme),
+        None => ColourScheme::parse("custom", s).map_err(|e| format!("--scheme: {}", e)),
+    }
+}
+
+// renders the cube after applying the algorithm without opening a window
+fn render_png(options: &Options, config: &Config, path: &str) -> error::Result<()> {
+    let mut renderer = Renderer::headless(options.width, options.height)?;
+    renderer.set_clear_colour(config.clear_colour);
+    renderer
+        .camera_mut()
+        .orbit(options.yaw, options.pitch, CAMERA_DISTANCE);
+
+    let mut cube = new_cube(options, config);
+    cube.apply(&options.alg).map_err(Error::Depth)?;
+    cube.skip_animation();
+    renderer.add_object(cube);
+
+    renderer.save_png(path)
+}
+
+// creates the cube with the settings from the config and command line
+fn new_cube(options: &Options, config: &Config) -> Cube {
+    let mut cube = Cube::new(options.size);
+    let diagnostics = cube.configure(config);
+    print_diagnostics(&diagnostics);
+    if let Some(scheme) = &options.scheme {
+        cube.set_scheme(scheme.clone());
+    }
+    cube
+}
+
+fn print_diagnostics<D: std::fmt::Display>(diagnostics: &[D]) {
+    let path = Config::path().unwrap_or_default();
+    for diagnostic in diagnostics {
+        eprintln!("{}: {}", path.display(), diagnostic);
+    }
+}
+
+// opens the window and runs until it is closed
+fn run(options: &Options, config: &Config) -> error::Result<()> {
+    let mut renderer = Renderer::windowed(config.width, config.height, config.vsync)?;
+    renderer.set_clear_colour(config.clear_colour);
+    if options.watch_shaders {
+        renderer.watch_shaders(manifest::asset_root().join("shaders"));
+    }
+    let mut cube = new_cube(options, config);
+    cube.prepare_solver();
+    renderer.add_object(cube);
+    renderer.main_loop()
+}
+
+impl Options {
+    fn uses_history(&self) -> bool {
+        self.stats
+            || self.export_csv.is_some()
+            || self.import_cstimer.is_some()
+            || self.export_cstimer.is_some()
+    }
+}
+
+// wraps an IO error with the file it happened to
+fn io_error(path: &Path) -> impl FnOnce(io::Error) -> Error + '_ {
+    move |error| Error::Io {
+        path: path.to_path_buf(),
+        error,
+    }
+}
+
+// imports into, prints statistics for or exports the solve history
+fn history(options: &Options) -> error::Result<()> {
+    let mut history = History::open_default().map_err(Error::History)?;
+
+    if let Some(path) = &options.import_cstimer {
+        let path = Path::new(path);
+        let json = fs::read_to_string(path).map_err(io_error(path))?;
+        let entries = cstimer::import(&json).map_err(|error| Error::CsTimer {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        let total = entries.len();
+        let added = history.merge(entries).map_err(io_error(history.path()))?;
+        println!("imported {} of {} solves", added, total);
+    }
+
+    if options.stats {
+        print!("{}", history.stats());
+    }
+    if let Some(path) = &options.export_csv {
+        let path = Path::new(path);
+        File::create(path)
+            .and_then(|file| history.write_csv(BufWriter::new(file)))
+            .map_err(io_error(path))?;
+    }
+    if let Some(path) = &options.export_cstimer {
+        let path = Path::new(path);
+        fs::write(path, cstimer::export(history.entries())).map_err(io_error(path))?;
+    }
+    Ok(())
+}
+
 fn main() {
-    let mut renderer = Renderer::new();
-    let mut manager = ResourceManager::new();
-    renderer.add_object(Cube::new(&mut manager));
-    renderer.main_loop();
+    let options = parse_args().unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(1);
+    });
+
+    if options.uses_history() {
+        if let Err(e) = history(&options) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    // a config that cannot be read is reported but the defaults still work
+    let (config, diagnostics) = Config::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        (Config::default(), Vec::new())
+    });
+    print_diagnostics(&diagnostics);
+
+    if let Some(path) = &options.png {
+        if let Err(e) = render_png(&options, &config, path) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Err(e) = run(&options, &config) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 }
@@ -60,6 +60,14 @@ impl ModelBuffer {
         VertexArray::unbind();
     }
 
+    /// Changes the colour the model is lit with. Models without a material
+    /// keep the shader's current colour.
+    pub fn set_diffuse(&mut self, diffuse: [f32; 3]) {
+        if let Some(material) = &mut self.material {
+            material.diffuse = diffuse;
+        }
+    }
+
     pub fn draw(&self, renderer: &Renderer) {
         self.vao.bind();
         self.indices.bind();
@@ -1,6 +1,7 @@
+use crate::components::picking::Ray;
 use crate::components::transform::Transform;
 
-use nalgebra::Matrix4;
+use nalgebra::{Matrix4, Point3, Vector3};
 
 const DEFAULT_FOV: f32 = 70.0;
 const DEFAULT_ASPECT_RATIO: f32 = 16.0 / 9.0;
@@ -26,7 +27,35 @@ impl Default for Camera {
 }
 
 impl Camera {
+    /// Places the camera `distance` away from the origin looking at it.
+    /// With yaw and pitch at 0 the camera faces the front of the cube,
+    /// positive yaw moves it towards the right face and positive pitch
+    /// towards the top. Angles are in degrees.
+    pub fn orbit(&mut self, yaw: f32, pitch: f32, distance: f32) {
+        let (yaw, pitch) = (yaw.to_radians(), pitch.to_radians());
+        // the world has y pointing down and the cube's front facing -z
+        let pos = Vector3::new(
+            pitch.cos() * yaw.sin(),
+            -pitch.sin(),
+            -pitch.cos() * yaw.cos(),
+        ) * distance;
+
+        self.transform.pos = pos.into();
+        self.transform.look_at(Vector3::zeros());
+    }
+
     pub fn projection(&self) -> Matrix4<f32> {
         Matrix4::new_perspective(self.asxspect_ratio, self.fov, self.near, self.far)
     }
+
+    /// The world space ray through a point on the screen given in normalized
+    /// device coordinates, with y pointing up.
+    pub fn ray(&self, x: f32, y: f32) -> Ray {
+        let inverse = (self.projection() * self.transform.view())
+            .try_inverse()
+            .unwrap();
+        let near = inverse.transform_point(&Point3::new(x, y, -1.0));
+        let far = inverse.transform_point(&Point3::new(x, y, 1.0));
+        Ray::new(near, far - near)
+    }
 }
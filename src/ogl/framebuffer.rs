@@ -0,0 +1,118 @@
+use crate::error::{self, Error};
+
+use gl::types::*;
+
+/// An offscreen render target with a colour and depth attachment.
+pub struct Framebuffer {
+    fbo: GLuint,
+    color: GLuint,
+    depth: GLuint,
+    width: i32,
+    height: i32,
+}
+
+impl Framebuffer {
+    pub fn new(width: i32, height: i32) -> error::Result<Framebuffer> {
+        let mut fb = Framebuffer {
+            fbo: 0,
+            color: 0,
+            depth: 0,
+            width,
+            height,
+        };
+
+        unsafe {
+            gl::GenFramebuffers(1, &mut fb.fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fb.fbo);
+
+            gl::GenRenderbuffers(1, &mut fb.color);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, fb.color);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::RGBA8, width, height);
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::RENDERBUFFER,
+                fb.color,
+            );
+
+            gl::GenRenderbuffers(1, &mut fb.depth);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, fb.depth);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, width, height);
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::RENDERBUFFER,
+                fb.depth,
+            );
+
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(Error::Framebuffer(format!(
+                    "incomplete framebuffer: 0x{:x}",
+                    status
+                )));
+            }
+        }
+
+        Ok(fb)
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+        }
+    }
+
+    pub fn unbind() {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    /// Reads the colour attachment as tightly packed RGB rows, top row
+    /// first.
+    pub fn read_rgb(&self) -> Vec<u8> {
+        let row = self.width as usize * 3;
+        let mut data = vec![0; row * self.height as usize];
+
+        self.bind();
+        unsafe {
+            gl::Finish();
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                self.width,
+                self.height,
+                gl::RGB,
+                gl::UNSIGNED_BYTE,
+                data.as_mut_ptr() as *mut GLvoid,
+            );
+        }
+        Framebuffer::unbind();
+
+        // GL returns the bottom row first
+        let mut flipped = Vec::with_capacity(data.len());
+        for line in data.chunks(row).rev() {
+            flipped.extend_from_slice(line);
+        }
+        flipped
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteRenderbuffers(1, &self.color);
+            gl::DeleteRenderbuffers(1, &self.depth);
+            gl::DeleteFramebuffers(1, &self.fbo);
+        }
+    }
+}
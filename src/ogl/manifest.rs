@@ -0,0 +1,121 @@
+//! Finding assets on disk. Assets are listed by ID in `manifest.toml` in the
+//! asset root so code never needs to know where a file lives.
+
+use crate::error::{self, Error};
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MANIFEST_FILE: &str = "manifest.toml";
+/// Overrides where assets are loaded from.
+pub const ASSET_ROOT_VAR: &str = "CUBE_ASSETS";
+
+/// The directory assets are loaded from. This is `$CUBE_ASSETS` when set,
+/// otherwise the first `assets` directory with a manifest found going up from
+/// the executable, so both installed and `cargo run` builds find theirs, and
+/// lastly `assets` in the working directory.
+pub fn asset_root() -> PathBuf {
+    if let Some(root) = env::var_os(ASSET_ROOT_VAR).filter(|r| !r.is_empty()) {
+        return PathBuf::from(root);
+    }
+
+    let exe = env::current_exe().ok();
+    let found = exe.as_ref().and_then(|exe| {
+        exe.ancestors()
+            .skip(1)
+            .map(|dir| dir.join("assets"))
+            .find(|dir| dir.join(MANIFEST_FILE).is_file())
+    });
+    found.unwrap_or_else(|| PathBuf::from("assets"))
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    models: HashMap<String, PathBuf>,
+    #[serde(default)]
+    textures: HashMap<String, PathBuf>,
+}
+
+impl Manifest {
+    pub fn parse(s: &str) -> Result<Manifest, toml::de::Error> {
+        toml::from_str(s)
+    }
+
+    /// Reads the manifest in the asset root.
+    pub fn load(root: &Path) -> error::Result<Manifest> {
+        let path = root.join(MANIFEST_FILE);
+        let s = fs::read_to_string(&path).map_err(|error| Error::Io {
+            path: path.clone(),
+            error,
+        })?;
+        Manifest::parse(&s).map_err(|error| Error::Manifest { path, error })
+    }
+
+    /// The path of a model relative to the asset root.
+    pub fn model(&self, id: &str) -> error::Result<&Path> {
+        Self::find(&self.models, "model", id)
+    }
+
+    /// The path of a texture relative to the asset root.
+    pub fn texture(&self, id: &str) -> error::Result<&Path> {
+        Self::find(&self.textures, "texture", id)
+    }
+
+    fn find<'a>(
+        assets: &'a HashMap<String, PathBuf>,
+        kind: &'static str,
+        id: &str,
+    ) -> error::Result<&'a Path> {
+        assets
+            .get(id)
+            .map(PathBuf::as_path)
+            .ok_or_else(|| Error::UnknownAsset {
+                kind,
+                id: id.to_string(),
+            })
+    }
+
+    /// Every listed file that does not exist under `root`.
+    pub fn missing_files(&self, root: &Path) -> Vec<PathBuf> {
+        let mut missing: Vec<_> = self
+            .models
+            .values()
+            .chain(self.textures.values())
+            .map(|path| root.join(path))
+            .filter(|path| !path.is_file())
+            .collect();
+        missing.sort();
+        missing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest() {
+        let manifest = Manifest::parse(
+            r#"
+            [models]
+            cube = "obj/rcube.obj"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(manifest.model("cube").unwrap(), Path::new("obj/rcube.obj"));
+        assert_eq!(
+            manifest.texture("cube").unwrap_err().to_string(),
+            "no texture with the ID \"cube\" in the asset manifest"
+        );
+        assert!(Manifest::parse("[models]\ncube = 5").is_err());
+
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        let shipped = Manifest::load(&root).unwrap();
+        assert!(shipped.texture("missing").is_ok());
+        assert_eq!(shipped.missing_files(&root), Vec::<PathBuf>::new());
+    }
+}
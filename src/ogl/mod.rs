@@ -1,11 +1,15 @@
 pub mod buffer;
 pub mod camera;
 pub mod color_buffer;
+pub mod framebuffer;
+pub mod manifest;
 pub mod normal;
+pub mod orbit;
 pub mod program;
 pub mod render;
 pub mod resources;
 pub mod shader;
+pub mod shader_watch;
 pub mod texture;
 pub mod uv;
 pub mod vertex;
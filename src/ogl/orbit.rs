@@ -0,0 +1,113 @@
+use crate::ogl::camera::Camera;
+
+use sdl2::event::Event;
+use sdl2::keyboard::{KeyboardState, Scancode};
+
+const DEFAULT_DISTANCE: f32 = 14.0;
+const MIN_DISTANCE: f32 = 6.0;
+const MAX_DISTANCE: f32 = 40.0;
+const MAX_PITCH: f32 = 85.0;
+// degrees turned per pixel dragged
+const DRAG_SPEED: f32 = 0.4;
+// degrees turned per second while an arrow key is held
+const KEY_SPEED: f32 = 120.0;
+// how much one notch of the mouse wheel scales the distance
+const ZOOM_STEP: f32 = 0.9;
+// how quickly the camera catches up with its target, higher is snappier
+const DAMPING: f32 = 12.0;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct View {
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+}
+
+/// Moves the camera around the origin. Dragging with the right mouse button
+/// or holding the arrow keys orbits and the mouse wheel zooms. The camera
+/// eases towards where it was sent rather than jumping there.
+pub struct OrbitController {
+    target: View,
+    current: View,
+}
+
+impl Default for OrbitController {
+    fn default() -> OrbitController {
+        let view = View {
+            yaw: 0.0,
+            pitch: 0.0,
+            distance: DEFAULT_DISTANCE,
+        };
+        OrbitController {
+            target: view,
+            current: view,
+        }
+    }
+}
+
+impl OrbitController {
+    /// Moves the target by the given angles in degrees, keeping the pitch
+    /// short of straight up or down.
+    pub fn rotate(&mut self, yaw: f32, pitch: f32) {
+        self.target.yaw = (self.target.yaw + yaw) % 360.0;
+        self.target.pitch = (self.target.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Scales the distance to the origin, zooming in when `factor` is below 1.
+    pub fn zoom(&mut self, factor: f32) {
+        self.target.distance = (self.target.distance * factor).clamp(MIN_DISTANCE, MAX_DISTANCE);
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::MouseMotion {
+                mousestate,
+                xrel,
+                yrel,
+                ..
+            } if mousestate.right() => {
+                // the cube follows the cursor, so the camera goes the other way
+                self.rotate(-xrel as f32 * DRAG_SPEED, yrel as f32 * DRAG_SPEED);
+            }
+            Event::MouseWheel { y, .. } => self.zoom(ZOOM_STEP.powi(y)),
+            _ => {}
+        }
+    }
+
+    pub fn handle_input(&mut self, keyboard: &KeyboardState, dt: f32) {
+        let step = KEY_SPEED * dt;
+        if keyboard.is_scancode_pressed(Scancode::Left) {
+            self.rotate(step, 0.0);
+        }
+        if keyboard.is_scancode_pressed(Scancode::Right) {
+            self.rotate(-step, 0.0);
+        }
+        if keyboard.is_scancode_pressed(Scancode::Up) {
+            self.rotate(0.0, -step);
+        }
+        if keyboard.is_scancode_pressed(Scancode::Down) {
+            self.rotate(0.0, step);
+        }
+    }
+
+    /// Eases the view `dt` seconds towards the target and moves the camera
+    /// there.
+    pub fn update(&mut self, camera: &mut Camera, dt: f32) {
+        let t = 1.0 - (-DAMPING * dt).exp();
+        let (current, target) = (&mut self.current, &self.target);
+
+        // take the short way round when the yaw wraps
+        let mut yaw = target.yaw - current.yaw;
+        if yaw > 180.0 {
+            yaw -= 360.0;
+        } else if yaw < -180.0 {
+            yaw += 360.0;
+        }
+
+        current.yaw = (current.yaw + yaw * t) % 360.0;
+        current.pitch += (target.pitch - current.pitch) * t;
+        current.distance += (target.distance - current.distance) * t;
+
+        camera.orbit(current.yaw, current.pitch, current.distance);
+    }
+}
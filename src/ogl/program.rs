@@ -1,3 +1,4 @@
+use crate::error::{self, Error};
 use crate::ogl::shader::Shader;
 use crate::util::*;
 
@@ -16,7 +17,7 @@ impl Program {
         }
     }
 
-    pub fn from_shaders(shaders: &[Shader]) -> Result<Program, String> {
+    pub fn from_shaders(shaders: &[Shader]) -> error::Result<Program> {
         let program_id = unsafe { gl::CreateProgram() };
 
         for shader in shaders {
@@ -60,7 +61,7 @@ impl Program {
                 );
             }
 
-            Err(error.to_string_lossy().into_owned())
+            Err(Error::ShaderLink(error.to_string_lossy().into_owned()))
         }
     }
 
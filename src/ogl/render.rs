@@ -1,41 +1,78 @@
+use crate::components::picking::Ray;
+use crate::error::{self, Error};
 use crate::ogl::camera::Camera;
 use crate::ogl::color_buffer::ColorBuffer;
+use crate::ogl::framebuffer::Framebuffer;
+use crate::ogl::orbit::OrbitController;
 use crate::ogl::program::Program;
 use crate::ogl::shader::Shader;
+use crate::ogl::shader_watch::ShaderWatcher;
 use crate::ogl::viewport::Viewport;
 
 use nalgebra::{Matrix4, Vector3};
 use sdl2::event::{Event, WindowEvent};
-use sdl2::render::WindowCanvas;
 use sdl2::video::gl_attr::GLAttr;
+use sdl2::video::{GLContext, Window};
 use sdl2::EventPump;
 use sdl2::Sdl;
 use std::cell::RefCell;
+use std::env;
 use std::ffi::{c_void, CString};
+use std::io;
+use std::path::Path;
+use std::time::Instant;
+
+const WINDOW_WIDTH: u32 = 1600;
+const WINDOW_HEIGHT: u32 = 900;
+const WINDOW_TITLE: &str = "rust-sdl2 demo";
+const CLEAR_COLOUR: [f32; 3] = [0.5, 0.0, 0.5];
+// the shaders built into the binary, loaded from here when watching them
+const VERTEX_SHADER: &str = "shader.vs";
+const FRAGMENT_SHADER: &str = "shader.fs";
 
 pub trait WorldObject {
     fn on_render(&mut self, _renderer: &Renderer) {}
     fn on_tick(&mut self, _event_pump: &EventPump, _renderer: &Renderer) {}
     fn on_add(&mut self, _renderer: &Renderer) {}
-    fn on_event(&mut self, _event: &Event) {}
+    fn on_event(&mut self, _event: &Event, _renderer: &Renderer) {}
+
+    /// Text to show in the window title, such as a running timer.
+    fn status(&self) -> Option<String> {
+        None
+    }
 }
 
 pub struct Renderer {
-    canvas: WindowCanvas,
+    window: Window,
+    _context: GLContext,
     sdl: Sdl,
     viewport: Viewport,
     render_objects: Vec<Box<RefCell<dyn WorldObject>>>,
     camera: Camera,
+    orbit: OrbitController,
+    // seconds between the last two frames
+    frame_time: f32,
+    clear_colour: [f32; 3],
+    // set when rendering headless, frames are drawn here instead of the window
+    framebuffer: Option<Framebuffer>,
+    // set while shaders are being reloaded from disk as they change
+    shader_watcher: Option<ShaderWatcher>,
     pub program: Program,
 }
 
 impl Renderer {
-    fn configure_gl(gl_attr: &GLAttr) {
+    // must be called before the window is created for the attributes to
+    // apply to its context
+    fn configure_gl(gl_attr: &GLAttr, multisample: bool) {
         gl_attr.set_context_profile(sdl2::video::GLProfile::Core);
         gl_attr.set_context_version(4, 5);
-        gl_attr.set_multisample_buffers(1);
-        gl_attr.set_multisample_samples(8);
+        if multisample {
+            gl_attr.set_multisample_buffers(1);
+            gl_attr.set_multisample_samples(8);
+        }
+    }
 
+    fn enable_gl() {
         unsafe {
             gl::Enable(gl::DEPTH_TEST);
             gl::Enable(gl::MULTISAMPLE);
@@ -43,55 +80,149 @@ impl Renderer {
         }
     }
 
-    fn init_program() -> Program {
+    fn init_program() -> error::Result<Program> {
         let vert_shader = Shader::vert_from_cstr(
             &CString::new(include_str!("../../assets/shaders/shader.vs")).unwrap(),
-        )
-        .unwrap();
+        )?;
 
         let frag_shader = Shader::frag_from_cstr(
             &CString::new(include_str!("../../assets/shaders/shader.fs")).unwrap(),
-        )
-        .unwrap();
+        )?;
 
-        Program::from_shaders(&[vert_shader, frag_shader]).unwrap()
+        Program::from_shaders(&[vert_shader, frag_shader])
     }
 
-    pub fn new() -> Renderer {
-        let sdl = sdl2::init().unwrap();
-        let video = sdl.video().unwrap();
+    fn load_program(dir: &Path) -> error::Result<Program> {
+        let vert_shader = Shader::from_file(dir.join(VERTEX_SHADER), gl::VERTEX_SHADER)?;
+        let frag_shader = Shader::from_file(dir.join(FRAGMENT_SHADER), gl::FRAGMENT_SHADER)?;
+        Program::from_shaders(&[vert_shader, frag_shader])
+    }
+
+    /// Loads the shaders from `dir` instead of the ones built in, and
+    /// recompiles them whenever they are saved. If they fail to compile the
+    /// error is shown and the last working shaders are kept.
+    pub fn watch_shaders<P: AsRef<Path>>(&mut self, dir: P) {
+        let dir = dir.as_ref();
+        let paths = vec![dir.join(VERTEX_SHADER), dir.join(FRAGMENT_SHADER)];
+        self.shader_watcher = Some(ShaderWatcher::new(paths));
+        self.reload_shaders();
+    }
+
+    fn reload_shaders(&mut self) {
+        let dir = match &self.shader_watcher {
+            Some(watcher) => watcher.paths()[0].parent().unwrap_or(Path::new("")),
+            None => return,
+        };
+
+        match Self::load_program(dir) {
+            Ok(program) => {
+                program.use_program();
+                self.program = program;
+                println!("loaded shaders from {}", dir.display());
+            }
+            Err(e) => eprintln!("{}\nkeeping the last working shaders", e),
+        }
+    }
 
-        let window = video
-            .window("rust-sdl2 demo", 1600, 900)
-            .position_centered()
-            .resizable()
-            .opengl()
-            .build()
-            .unwrap();
+    fn create(width: u32, height: u32, headless: bool, vsync: bool) -> error::Result<Renderer> {
+        let sdl = sdl2::init().map_err(Error::Sdl)?;
+        let video = sdl.video().map_err(Error::Sdl)?;
+        Self::configure_gl(&video.gl_attr(), !headless);
 
+        let mut builder = video.window(WINDOW_TITLE, width, height);
+        builder.opengl();
+        if headless {
+            builder.hidden();
+        } else {
+            builder.position_centered().resizable();
+        }
+        let window = builder.build().map_err(|e| Error::Sdl(e.to_string()))?;
+
+        let context = window.gl_create_context().map_err(Error::Context)?;
         gl::load_with(|s| video.gl_get_proc_address(s) as *const c_void);
-        let canvas = window.into_canvas().present_vsync().build().unwrap();
-        let gl_attr = video.gl_attr();
-        Self::configure_gl(&gl_attr);
+        if !headless {
+            video
+                .gl_set_swap_interval(if vsync { 1 } else { 0 })
+                .map_err(Error::Context)?;
+        }
+        Self::enable_gl();
 
-        let viewport = Viewport::for_window(1600, 900);
+        let viewport = Viewport::for_window(width as i32, height as i32);
         viewport.use_viewport();
 
         let render_objects = Vec::new();
-        let mut camera = Camera::default();
-        camera.transform.translate(Vector3::new(0.0, 0.0, -14.0));
+        let mut camera = Camera {
+            asxspect_ratio: width as f32 / height as f32,
+            ..Camera::default()
+        };
+        let mut orbit = OrbitController::default();
+        orbit.update(&mut camera, 0.0);
 
-        let program = Self::init_program();
+        let program = Self::init_program()?;
         program.use_program();
 
-        Renderer {
+        Ok(Renderer {
+            window,
+            _context: context,
             sdl,
-            canvas,
             viewport,
             render_objects,
             camera,
+            orbit,
+            frame_time: 0.0,
+            clear_colour: CLEAR_COLOUR,
+            framebuffer: None,
+            shader_watcher: None,
             program,
+        })
+    }
+
+    pub fn new() -> error::Result<Renderer> {
+        Self::windowed(WINDOW_WIDTH, WINDOW_HEIGHT, true)
+    }
+
+    /// Opens a resizable window of the given size. With `vsync` frames are
+    /// shown in step with the display's refresh.
+    pub fn windowed(width: u32, height: u32, vsync: bool) -> error::Result<Renderer> {
+        Self::create(width, height, false, vsync)
+    }
+
+    /// Creates a renderer that draws into an offscreen framebuffer of the
+    /// given size for `save_png`. When there is no display SDL's offscreen
+    /// video driver is used, which gets a context through EGL, so this also
+    /// works on machines with only Mesa's software rasterizer.
+    pub fn headless(width: u32, height: u32) -> error::Result<Renderer> {
+        let has_display = ["DISPLAY", "WAYLAND_DISPLAY"]
+            .iter()
+            .any(|var| matches!(env::var_os(var), Some(v) if !v.is_empty()));
+        if !has_display && env::var_os("SDL_VIDEODRIVER").is_none() {
+            env::set_var("SDL_VIDEODRIVER", "offscreen");
         }
+
+        let mut renderer = Self::create(width, height, true, false)?;
+        renderer.framebuffer = Some(Framebuffer::new(width as i32, height as i32)?);
+        Ok(renderer)
+    }
+
+    /// Sets the colour drawn behind the objects.
+    pub fn set_clear_colour(&mut self, colour: [f32; 3]) {
+        self.clear_colour = colour;
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    /// The world space ray under a point of the window given in pixels.
+    pub fn screen_ray(&self, x: i32, y: i32) -> Ray {
+        let (width, height) = self.viewport.size();
+        let x = 2.0 * x as f32 / width as f32 - 1.0;
+        let y = 1.0 - 2.0 * y as f32 / height as f32;
+        self.camera.ray(x, y)
     }
 
     pub fn add_object<O: WorldObject + 'static>(&mut self, o: O) {
@@ -99,12 +230,34 @@ impl Renderer {
         self.render_objects.push(o);
     }
 
-    fn tick(&mut self, event_pump: &EventPump) {
+    /// Seconds since the previous frame, for animating independently of the
+    /// frame rate.
+    pub fn frame_time(&self) -> f32 {
+        self.frame_time
+    }
+
+    fn tick(&mut self, event_pump: &EventPump, dt: f32) {
+        self.frame_time = dt;
+        self.orbit.handle_input(&event_pump.keyboard_state(), dt);
+        self.orbit.update(&mut self.camera, dt);
+
         for object in &self.render_objects {
             object.borrow_mut().on_tick(event_pump, self);
         }
     }
 
+    // shows the status of the first object that has one after the title
+    fn update_title(&mut self) {
+        let title = match self.render_objects.iter().find_map(|o| o.borrow().status()) {
+            Some(status) => format!("{} - {}", WINDOW_TITLE, status),
+            None => WINDOW_TITLE.to_string(),
+        };
+        if self.window.title() != title {
+            // only fails for titles containing a NUL byte
+            let _ = self.window.set_title(&title);
+        }
+    }
+
     fn render(&mut self) {
         for object in &self.render_objects {
             object.borrow_mut().on_render(self);
@@ -112,8 +265,8 @@ impl Renderer {
     }
 
     fn event(&mut self, event: &Event) {
-        for object in &mut self.render_objects {
-            object.get_mut().on_event(event);
+        for object in &self.render_objects {
+            object.borrow_mut().on_event(event, self);
         }
     }
 
@@ -121,12 +274,61 @@ impl Renderer {
         self.program.set_mat4("model", model);
     }
 
-    pub fn main_loop(&mut self) {
-        let mut event_pump = self.sdl.event_pump().unwrap();
+    fn draw(&mut self) {
+        if let Some(framebuffer) = &self.framebuffer {
+            framebuffer.bind();
+        }
+
+        let color_buffer = ColorBuffer::from_color(Vector3::from(self.clear_colour));
+        color_buffer.use_color_buffer();
+        color_buffer.clear();
+
+        let i = 0.8;
+        let pos = self.camera.transform.pos;
+        self.program.set_3f("lightColor", [i, i, i]);
+        self.program.set_3f("lightPos", [0.0, -10.0, -10.0]);
+        self.program.set_3f("viewPos", [pos.x, pos.y, pos.z]);
+        self.program.set_mat4("view", self.camera.transform.view());
+        self.program
+            .set_mat4("projection", self.camera.projection());
+
+        self.render();
+        Framebuffer::unbind();
+    }
+
+    /// Draws a single frame and writes it to `path` as a PNG. Only available
+    /// on renderers created with `headless`.
+    pub fn save_png<P: AsRef<Path>>(&mut self, path: P) -> error::Result<()> {
+        self.draw();
+
+        let path = path.as_ref();
+        let framebuffer = self.framebuffer.as_ref().ok_or_else(|| Error::Io {
+            path: path.to_path_buf(),
+            error: io::Error::other("only headless renderers can save images"),
+        })?;
+        let (width, height) = framebuffer.size();
+        image::save_buffer_with_format(
+            path,
+            &framebuffer.read_rgb(),
+            width as u32,
+            height as u32,
+            image::RGB(8),
+            image::ImageFormat::PNG,
+        )
+        .map_err(|error| Error::Io {
+            path: path.to_path_buf(),
+            error,
+        })
+    }
+
+    pub fn main_loop(&mut self) -> error::Result<()> {
+        let mut event_pump = self.sdl.event_pump().map_err(Error::Sdl)?;
+        let mut last_frame = Instant::now();
 
         'running: loop {
             for event in event_pump.poll_iter() {
                 self.event(&event);
+                self.orbit.handle_event(&event);
                 match event {
                     Event::Window {
                         win_event: WindowEvent::Resized(w, h),
@@ -134,32 +336,42 @@ impl Renderer {
                     } => {
                         self.viewport.set_size(w, h);
                         self.viewport.use_viewport();
+                        self.camera.asxspect_ratio = w as f32 / h as f32;
                     }
                     Event::Quit { .. } => break 'running,
                     _ => {}
                 }
-
-                self.event(&event);
             }
 
-            self.tick(&event_pump);
+            let now = Instant::now();
+            let dt = now.duration_since(last_frame).as_secs_f32();
+            last_frame = now;
 
-            let color_buffer = ColorBuffer::from_color(Vector3::new(0.5, 0.0, 0.5));
-            color_buffer.use_color_buffer();
-            color_buffer.clear();
-
-            let i = 0.8;
-            let pos = self.camera.transform.pos;
-            self.program.set_3f("lightColor", [i, i, i]);
-            self.program.set_3f("lightPos", [0.0, -10.0, -10.0]);
-            self.program.set_3f("viewPos", [pos.x, pos.y, pos.z]);
-            self.program.set_mat4("view", self.camera.transform.view());
-            self.program
-                .set_mat4("projection", self.camera.projection());
+            if self
+                .shader_watcher
+                .as_mut()
+                .is_some_and(ShaderWatcher::poll)
+            {
+                self.reload_shaders();
+            }
 
-            self.render();
-            self.canvas.present();
+            self.tick(&event_pump, dt);
+            self.update_title();
+            self.draw();
+            self.window.gl_swap_window();
             //::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
         }
+
+        Ok(())
     }
 }
+
+/// A headless renderer for tests that need an OpenGL context. SDL can only
+/// be started once at a time, so the returned guard keeps other such tests
+/// waiting until it is dropped after the renderer.
+#[cfg(test)]
+pub fn test_renderer() -> (std::sync::MutexGuard<'static, ()>, Renderer) {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    (guard, Renderer::headless(64, 64).unwrap())
+}
@@ -1,128 +1,240 @@
+use crate::components::mesh::Mesh;
+use crate::error::{self, Error};
 use crate::ogl::buffer::ModelBuffer;
 use crate::ogl::buffer::{ArrayBuffer, ElementArrayBuffer, VertexArray};
 use crate::ogl::texture::Texture;
 
-use image::ImageError;
-use std::collections::hash_map::Entry;
+use crate::ogl::manifest::{self, Manifest};
+
 use std::collections::HashMap;
-use std::path::Path;
-use std::rc::Rc;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+use tobj::Material;
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
-pub enum Models {
-    Cube,
-    Spot,
-}
-
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
-pub enum Textures {
-    Cube,
-    Spot,
-}
+// shown in place of a texture that fails to load
+const MISSING_TEXTURE: &str = "missing";
 
+/// Loads models and textures by their ID in the asset manifest. Each asset
+/// is loaded once and shared while anything holds it. Its GL objects are
+/// freed as soon as the last holder drops it.
 pub struct ResourceManager {
-    models: HashMap<Models, Rc<Vec<ModelBuffer>>>,
-    textures: HashMap<Textures, Rc<Texture>>,
+    root: PathBuf,
+    manifest: Manifest,
+    models: HashMap<String, Weak<Vec<ModelBuffer>>>,
+    textures: HashMap<String, Weak<Texture>>,
 }
 
 impl ResourceManager {
-    pub fn new() -> ResourceManager {
-        ResourceManager {
+    /// Loads the manifest from the asset root found by `manifest::asset_root`.
+    pub fn new() -> error::Result<ResourceManager> {
+        ResourceManager::with_root(manifest::asset_root())
+    }
+
+    pub fn with_root<P: Into<PathBuf>>(root: P) -> error::Result<ResourceManager> {
+        let root = root.into();
+        let manifest = Manifest::load(&root)?;
+        Ok(ResourceManager {
+            root,
+            manifest,
             models: HashMap::new(),
             textures: HashMap::new(),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn load_model(&mut self, id: &str) -> error::Result<Rc<Vec<ModelBuffer>>> {
+        if let Some(model) = self.models.get(id).and_then(Weak::upgrade) {
+            return Ok(model);
         }
+
+        let path = self.root.join(self.manifest.model(id)?);
+        let model = Rc::new(load_obj(path)?);
+        self.models.insert(id.to_string(), Rc::downgrade(&model));
+        Ok(model)
     }
 
-    pub fn load_model(&mut self, model: Models) -> Result<Rc<Vec<ModelBuffer>>, tobj::LoadError> {
-        match self.models.entry(model) {
-            Entry::Occupied(e) => Ok(Rc::clone(e.get())),
-            Entry::Vacant(e) => {
-                let buffers = match model {
-                    Models::Spot => load_obj("assets/obj/spot_triangulated.obj")?,
-                    Models::Cube => load_obj("assets/obj/rcube.obj")?,
-                };
-                let buffers = Rc::new(buffers);
-                e.insert(Rc::clone(&buffers));
-                Ok(buffers)
+    /// Loads a texture, falling back to the missing texture if the image
+    /// cannot be read. Only an unknown ID or a missing fallback is an error.
+    pub fn load_texture(&mut self, id: &str) -> error::Result<Rc<Texture>> {
+        match self.load_texture_file(id) {
+            Err(e @ Error::Texture { .. }) if id != MISSING_TEXTURE => {
+                eprintln!("{}", e);
+                self.load_texture_file(MISSING_TEXTURE).map_err(|_| e)
             }
+            result => result,
         }
     }
 
-    pub fn load_texture(&mut self, texture: Textures) -> Result<Rc<Texture>, ImageError> {
-        match self.textures.entry(texture) {
-            Entry::Occupied(e) => Ok(Rc::clone(e.get())),
-            Entry::Vacant(e) => {
-                let img = match texture {
-                    Textures::Spot => image::open("assets/textures/spot_texture.png")?,
-                    Textures::Cube => image::open("assets/textures/cube.bmp")?,
-                };
-                let img = img.to_rgb();
-                let width = img.width();
-                let height = img.height();
-                let data = img.into_vec();
-
-                let texture = Texture::new();
-                texture.bind();
-                Texture::tex_image_2d(width, height, &data);
-                Texture::unbind();
-
-                let texture = Rc::new(texture);
-                e.insert(Rc::clone(&texture));
-                Ok(texture)
-            }
+    fn load_texture_file(&mut self, id: &str) -> error::Result<Rc<Texture>> {
+        if let Some(texture) = self.textures.get(id).and_then(Weak::upgrade) {
+            return Ok(texture);
         }
+
+        let path = self.root.join(self.manifest.texture(id)?);
+        let img = image::open(&path).map_err(|error| Error::Texture { path, error })?;
+        let img = img.to_rgb();
+        let width = img.width();
+        let height = img.height();
+        let data = img.into_vec();
+
+        let texture = Texture::new();
+        texture.bind();
+        Texture::tex_image_2d(width, height, &data);
+        Texture::unbind();
+
+        let texture = Rc::new(texture);
+        self.textures
+            .insert(id.to_string(), Rc::downgrade(&texture));
+        Ok(texture)
+    }
+
+    /// Forgets assets that are no longer held anywhere. Their GL objects
+    /// were already freed when the last holder dropped them.
+    pub fn unload_unused(&mut self) {
+        self.models.retain(|_, model| model.strong_count() > 0);
+        self.textures
+            .retain(|_, texture| texture.strong_count() > 0);
+    }
+
+    /// How many models and textures are currently loaded.
+    pub fn loaded(&self) -> (usize, usize) {
+        let models = self.models.values().filter(|m| m.strong_count() > 0);
+        let textures = self.textures.values().filter(|t| t.strong_count() > 0);
+        (models.count(), textures.count())
     }
 }
 
-fn load_obj<P: AsRef<Path>>(p: P) -> Result<Vec<ModelBuffer>, tobj::LoadError> {
-    let (models, materials) = tobj::load_obj(p.as_ref())?;
+fn load_obj<P: AsRef<Path>>(p: P) -> error::Result<Vec<ModelBuffer>> {
+    let (models, materials) = tobj::load_obj(p.as_ref()).map_err(|error| Error::Model {
+        path: p.as_ref().to_path_buf(),
+        error,
+    })?;
 
     let mut buffers = Vec::with_capacity(models.len());
 
     for model in models {
-        let mesh = &model.mesh;
+        let mesh = model.mesh;
+        let material = mesh.material_id.map(|id| materials[id].clone());
 
-        let vao = VertexArray::new();
-
-        let vertex_buffer = ArrayBuffer::new();
-        vertex_buffer.bind();
-        ArrayBuffer::buffer_data(&mesh.positions);
-        ArrayBuffer::unbind();
+        let texcoords = mesh
+            .texcoords
+            .chunks(2)
+            .flat_map(|uv| vec![uv[0], 1.0 - uv[1]])
+            .collect();
+
+        let mesh = Mesh {
+            positions: mesh.positions,
+            normals: mesh.normals,
+            texcoords,
+            indices: mesh.indices,
+        };
+        buffers.push(upload_mesh(&mesh, material));
+    }
 
-        let element_buffer = ElementArrayBuffer::new();
-        element_buffer.bind();
-        ElementArrayBuffer::buffer_data(&mesh.indices);
-        ElementArrayBuffer::unbind();
+    Ok(buffers)
+}
 
-        let normal_buffer = ArrayBuffer::new();
-        normal_buffer.bind();
-        ArrayBuffer::buffer_data(&mesh.normals);
-        ArrayBuffer::unbind();
+/// Uploads a mesh to the GPU.
+pub fn upload_mesh(mesh: &Mesh, material: Option<Material>) -> ModelBuffer {
+    let vao = VertexArray::new();
+
+    let vertex_buffer = ArrayBuffer::new();
+    vertex_buffer.bind();
+    ArrayBuffer::buffer_data(&mesh.positions);
+    ArrayBuffer::unbind();
+
+    let element_buffer = ElementArrayBuffer::new();
+    element_buffer.bind();
+    ElementArrayBuffer::buffer_data(&mesh.indices);
+    ElementArrayBuffer::unbind();
+
+    let normal_buffer = ArrayBuffer::new();
+    normal_buffer.bind();
+    ArrayBuffer::buffer_data(&mesh.normals);
+    ArrayBuffer::unbind();
+
+    let uv_buffer = ArrayBuffer::new();
+    uv_buffer.bind();
+    ArrayBuffer::buffer_data(&mesh.texcoords);
+    ArrayBuffer::unbind();
+
+    ModelBuffer::new(
+        vao,
+        vertex_buffer,
+        element_buffer,
+        normal_buffer,
+        uv_buffer,
+        mesh.indices.len(),
+        material,
+    )
+}
 
-        let uv_buffer = ArrayBuffer::new();
-        uv_buffer.bind();
-        let uvs = mesh
-            .texcoords
-            .chunks(2)
-            .map(|uv| [uv[0], 1.0 - uv[1]])
-            .collect::<Vec<_>>();
-        ArrayBuffer::buffer_data(&uvs);
-        ArrayBuffer::unbind();
-
-        let material = model.mesh.material_id.map(|id| materials[id].clone());
-
-        let model_buffer = ModelBuffer::new(
-            vao,
-            vertex_buffer,
-            element_buffer,
-            normal_buffer,
-            uv_buffer,
-            mesh.indices.len(),
-            material,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ogl::render::test_renderer;
+    use std::fs;
+
+    // an asset root whose manifest lists a texture that is not on disk
+    fn broken_root(name: &str) -> PathBuf {
+        let missing = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/textures/missing.jpg");
+        let root = std::env::temp_dir().join(format!("cube-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let manifest = format!(
+            "[textures]\nbroken = \"broken.png\"\nmissing = {:?}\n",
+            missing
         );
+        fs::write(root.join("manifest.toml"), manifest).unwrap();
+        root
+    }
 
-        buffers.push(model_buffer);
+    #[test]
+    fn unknown_asset() {
+        let mut resources = ResourceManager::with_root(broken_root("unknown")).unwrap();
+        match resources.load_texture("chalet") {
+            Err(Error::UnknownAsset { kind, id }) => {
+                assert_eq!((kind, &*id), ("texture", "chalet"))
+            }
+            _ => panic!("expected an unknown texture"),
+        }
+        match resources.load_model("cube") {
+            Err(Error::UnknownAsset { kind, id }) => assert_eq!((kind, &*id), ("model", "cube")),
+            _ => panic!("expected an unknown model"),
+        }
+        fs::remove_dir_all(resources.root()).unwrap();
     }
 
-    Ok(buffers)
+    #[test]
+    fn missing_texture_fallback() {
+        let (_lock, _renderer) = test_renderer();
+        let mut resources = ResourceManager::with_root(broken_root("fallback")).unwrap();
+
+        let broken = resources.load_texture("broken").unwrap();
+        let missing = resources.load_texture("missing").unwrap();
+        assert!(Rc::ptr_eq(&broken, &missing));
+        assert_eq!(resources.loaded(), (0, 1));
+        fs::remove_dir_all(resources.root()).unwrap();
+    }
+
+    #[test]
+    fn unload_after_last_handle() {
+        let (_lock, _renderer) = test_renderer();
+        let mut resources = ResourceManager::new().unwrap();
+
+        let first = resources.load_texture("missing").unwrap();
+        let second = resources.load_texture("missing").unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+        assert_eq!(resources.loaded(), (0, 1));
+
+        drop(first);
+        assert_eq!(resources.loaded(), (0, 1));
+        drop(second);
+        assert_eq!(resources.loaded(), (0, 0));
+
+        resources.unload_unused();
+        assert!(resources.textures.is_empty());
+    }
 }
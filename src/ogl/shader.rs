@@ -3,6 +3,9 @@ use crate::util::*;
 
 use gl::types::{GLchar, GLuint};
 use std::ffi::{CStr, CString};
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::ptr;
 
 #[derive(Debug)]
@@ -26,6 +29,27 @@ impl Shader {
         Self::from_cstr(&s, kind)
     }
 
+    /// Compiles the source in a file, naming the file in any error.
+    pub fn from_file<P: AsRef<Path>>(path: P, kind: GLuint) -> error::Result<Shader> {
+        let path = path.as_ref();
+        let io_error = |error| Error::Io {
+            path: path.to_path_buf(),
+            error,
+        };
+
+        let source = fs::read_to_string(path).map_err(io_error)?;
+        let source = CString::new(source)
+            .map_err(|e| io_error(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+        Self::from_cstr(&source, kind).map_err(|e| match e {
+            Error::ShaderCompile { log, source, .. } => Error::ShaderCompile {
+                name: path.display().to_string(),
+                log,
+                source,
+            },
+            e => e,
+        })
+    }
+
     pub fn vert_from_cstr(s: &CStr) -> error::Result<Shader> {
         Self::from_cstr(s, gl::VERTEX_SHADER)
     }
@@ -61,9 +85,16 @@ impl Shader {
                 gl::GetShaderInfoLog(id, len, ptr::null_mut(), error.as_ptr() as *mut GLchar);
             }
 
-            Err(Error::ShaderCompileFail(
-                error.to_string_lossy().into_owned(),
-            ))
+            let name = match kind {
+                gl::VERTEX_SHADER => "vertex shader",
+                gl::FRAGMENT_SHADER => "fragment shader",
+                _ => "shader",
+            };
+            Err(Error::ShaderCompile {
+                name: name.to_string(),
+                log: error.to_string_lossy().into_owned(),
+                source: s.to_string_lossy().into_owned(),
+            })
         }
     }
 }
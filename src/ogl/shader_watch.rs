@@ -0,0 +1,54 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// how often the files are checked, as checking every frame is wasteful
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Notices when shader sources on disk are changed by polling their
+/// modification times.
+pub struct ShaderWatcher {
+    paths: Vec<PathBuf>,
+    modified: Vec<Option<SystemTime>>,
+    last_poll: Instant,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl ShaderWatcher {
+    pub fn new(paths: Vec<PathBuf>) -> ShaderWatcher {
+        let modified = paths.iter().map(|p| modified(p)).collect();
+        ShaderWatcher {
+            paths,
+            modified,
+            last_poll: Instant::now(),
+        }
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Whether any of the files has changed since the last time this
+    /// returned true.
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+
+        let mut changed = false;
+        for (path, last) in self.paths.iter().zip(&mut self.modified) {
+            let now = modified(path);
+            // a file that is briefly missing while an editor saves it is
+            // picked up again once it is back
+            if now.is_some() && now != *last {
+                *last = now;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
@@ -13,6 +13,16 @@ impl Texture {
         Texture(texture_id)
     }
 
+    /// A 1x1 texture of a single colour, for meshes without texture
+    /// coordinates.
+    pub fn solid(color: [u8; 3]) -> Texture {
+        let texture = Texture::new();
+        texture.bind();
+        Texture::tex_image_2d(1, 1, &color);
+        Texture::unbind();
+        texture
+    }
+
     pub fn bind(&self) {
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, self.0);
@@ -12,6 +12,10 @@ impl Viewport {
         self.0.w = h;
     }
 
+    pub fn size(&self) -> (i32, i32) {
+        (self.0.z, self.0.w)
+    }
+
     pub fn use_viewport(&self) {
         unsafe {
             gl::Viewport(self.0.x, self.0.y, self.0.z, self.0.w);
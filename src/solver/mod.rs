@@ -0,0 +1,356 @@
+//! Two-phase solver for the 3x3 cube.
+//!
+//! Phase 1 brings the cube into the subgroup generated by
+//! `<U, D, R2, L2, F2, B2>` by solving corner twist, edge flip and the
+//! position of the middle slice edges. Phase 2 then solves the cube using
+//! only moves from that subgroup.
+
+pub mod scramble;
+mod tables;
+
+use crate::components::algorithm::{Algorithm, Move, Turn};
+use crate::components::cubie::{CubieCube, StateError, MOVES};
+use crate::components::stickers::Stickers;
+use crate::solver::tables::{Tables, N_FLIP, N_MOVES, N_SLICE_PERM, N_TWIST, PHASE2_MOVES};
+use crate::util;
+
+use std::fmt;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const DEFAULT_MAX_LENGTH: usize = 22;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+static AXIS_TURNS: [Turn; 6] = [
+    Turn::Up,
+    Turn::Right,
+    Turn::Front,
+    Turn::Down,
+    Turn::Left,
+    Turn::Back,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    UnsupportedSize(usize),
+    InvalidState(StateError),
+    Timeout,
+    /// The search tables are still being generated in the background.
+    TablesPending,
+    /// Generating the search tables failed. They are generated again the
+    /// next time the solver is prepared.
+    TablesFailed,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::UnsupportedSize(size) => {
+                write!(f, "only 3x3 cubes can be solved, not {0}x{0}", size)
+            }
+            SolveError::InvalidState(e) => write!(f, "the cube state is not solvable: {}", e),
+            SolveError::Timeout => f.write_str("no solution found within the time limit"),
+            SolveError::TablesPending => f.write_str("the solver tables are still being generated"),
+            SolveError::TablesFailed => f.write_str("failed to generate the solver tables"),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SolveError::InvalidState(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+pub struct Solver {
+    tables: Tables,
+    pub max_length: usize,
+    pub timeout: Duration,
+}
+
+impl Solver {
+    /// Generates the search tables, reusing the tables cached on disk when
+    /// available.
+    pub fn new() -> Solver {
+        Solver {
+            tables: Tables::load(util::cache_dir().as_deref()),
+            max_length: DEFAULT_MAX_LENGTH,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn solve(&self, stickers: &Stickers) -> Result<Algorithm, SolveError> {
+        if stickers.size() != 3 {
+            return Err(SolveError::UnsupportedSize(stickers.size()));
+        }
+        stickers.validate().map_err(SolveError::InvalidState)?;
+        let cube = CubieCube::from_facelets(&stickers.facelets().unwrap()).unwrap();
+
+        let mut search = Search {
+            tables: &self.tables,
+            cube,
+            moves: Vec::with_capacity(self.max_length),
+            deadline: Instant::now() + self.timeout,
+            nodes: 0,
+            timed_out: false,
+        };
+
+        let twist = cube.twist();
+        let flip = cube.flip();
+        let slice = cube.slice();
+
+        for depth1 in 0..=self.max_length {
+            if search.phase1(twist, flip, slice, depth1, self.max_length) {
+                return Ok(search.moves.iter().map(|&m| to_move(m)).collect());
+            }
+            if search.timed_out {
+                return Err(SolveError::Timeout);
+            }
+        }
+
+        Err(SolveError::Timeout)
+    }
+}
+
+enum State {
+    Generating(JoinHandle<Solver>),
+    Ready(Box<Solver>),
+    Failed,
+}
+
+/// A `Solver` whose tables are generated on a background thread, so
+/// starting it does not stall the caller.
+pub struct BackgroundSolver {
+    generate: fn() -> Solver,
+    state: State,
+}
+
+impl BackgroundSolver {
+    pub fn spawn() -> BackgroundSolver {
+        BackgroundSolver::with(Solver::new)
+    }
+
+    fn with(generate: fn() -> Solver) -> BackgroundSolver {
+        BackgroundSolver {
+            generate,
+            state: State::Generating(thread::spawn(generate)),
+        }
+    }
+
+    /// Returns the solver once its tables are ready.
+    pub fn get(&mut self) -> Result<&Solver, SolveError> {
+        if let State::Generating(thread) = &self.state {
+            if !thread.is_finished() {
+                return Err(SolveError::TablesPending);
+            }
+            let thread = match std::mem::replace(&mut self.state, State::Failed) {
+                State::Generating(thread) => thread,
+                _ => unreachable!(),
+            };
+            if let Ok(solver) = thread.join() {
+                self.state = State::Ready(Box::new(solver));
+            }
+        }
+
+        match &self.state {
+            State::Ready(solver) => Ok(solver.as_ref()),
+            _ => Err(SolveError::TablesFailed),
+        }
+    }
+
+    /// Starts generating the tables again if the last attempt failed.
+    pub fn retry(&mut self) {
+        if let State::Failed = self.state {
+            self.state = State::Generating(thread::spawn(self.generate));
+        }
+    }
+}
+
+fn to_move(m: usize) -> Move {
+    let turn = AXIS_TURNS[m / 3];
+    match m % 3 {
+        0 => Move::new(turn, false, false, false),
+        1 => Move::new(turn, false, true, false),
+        _ => Move::new(turn, true, false, false),
+    }
+}
+
+// moves on the same axis are never consecutive and opposite faces are
+// always turned in a fixed order
+fn skip_move(moves: &[usize], m: usize) -> bool {
+    match moves.last() {
+        Some(&last) => {
+            let (axis, last_axis) = (m / 3, last / 3);
+            axis == last_axis || axis + 3 == last_axis
+        }
+        None => false,
+    }
+}
+
+struct Search<'a> {
+    tables: &'a Tables,
+    cube: CubieCube,
+    moves: Vec<usize>,
+    deadline: Instant,
+    nodes: usize,
+    timed_out: bool,
+}
+
+impl<'a> Search<'a> {
+    fn out_of_time(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes & 0xfff == 0 && Instant::now() > self.deadline {
+            self.timed_out = true;
+        }
+        self.timed_out
+    }
+
+    fn phase1(&mut self, twist: usize, flip: usize, slice: usize, togo: usize, max: usize) -> bool {
+        if togo == 0 {
+            // a phase 1 solution ending in a phase 2 move was already tried
+            // at a shorter depth
+            let ends_in_phase2 = matches!(self.moves.last(), Some(m) if PHASE2_MOVES.contains(m));
+            return twist == 0
+                && flip == 0
+                && slice == 0
+                && !ends_in_phase2
+                && self.start_phase2(max);
+        }
+
+        if self.out_of_time() {
+            return false;
+        }
+
+        let t = self.tables;
+        for m in 0..N_MOVES {
+            if skip_move(&self.moves, m) {
+                continue;
+            }
+
+            let twist = t.twist_move[twist * N_MOVES + m] as usize;
+            let flip = t.flip_move[flip * N_MOVES + m] as usize;
+            let slice = t.slice_move[slice * N_MOVES + m] as usize;
+
+            let dist = t.slice_twist_prun[slice * N_TWIST + twist]
+                .max(t.slice_flip_prun[slice * N_FLIP + flip]) as usize;
+            if dist >= togo {
+                continue;
+            }
+
+            self.moves.push(m);
+            if self.phase1(twist, flip, slice, togo - 1, max) {
+                return true;
+            }
+            self.moves.pop();
+
+            if self.timed_out {
+                return false;
+            }
+        }
+
+        false
+    }
+
+    fn start_phase2(&mut self, max: usize) -> bool {
+        let mut cube = self.cube;
+        for &m in &self.moves {
+            for _ in 0..=m % 3 {
+                cube = cube.multiply(&MOVES[m / 3]);
+            }
+        }
+
+        let corner = cube.corner_perm();
+        let edge8 = cube.edge8_perm();
+        let slice_perm = cube.slice_perm();
+        let depth1 = self.moves.len();
+
+        for depth2 in 0..=max - depth1 {
+            if self.phase2(corner, edge8, slice_perm, depth2) {
+                return true;
+            }
+            if self.timed_out {
+                return false;
+            }
+        }
+
+        false
+    }
+
+    fn phase2(&mut self, corner: usize, edge8: usize, slice_perm: usize, togo: usize) -> bool {
+        if togo == 0 {
+            return corner == 0 && edge8 == 0 && slice_perm == 0;
+        }
+
+        if self.out_of_time() {
+            return false;
+        }
+
+        let t = self.tables;
+        for &m in &PHASE2_MOVES {
+            if skip_move(&self.moves, m) {
+                continue;
+            }
+
+            let corner = t.corner_move[corner * N_MOVES + m] as usize;
+            let edge8 = t.edge8_move[edge8 * N_MOVES + m] as usize;
+            let slice_perm = t.slice_perm_move[slice_perm * N_MOVES + m] as usize;
+
+            let dist = t.corner_slice_prun[corner * N_SLICE_PERM + slice_perm]
+                .max(t.edge8_slice_prun[edge8 * N_SLICE_PERM + slice_perm])
+                as usize;
+            if dist >= togo {
+                continue;
+            }
+
+            self.moves.push(m);
+            if self.phase2(corner, edge8, slice_perm, togo - 1) {
+                return true;
+            }
+            self.moves.pop();
+
+            if self.timed_out {
+                return false;
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_tables() {
+        let mut solver = BackgroundSolver::with(|| panic!("no tables"));
+        while solver.get().err() == Some(SolveError::TablesPending) {
+            thread::yield_now();
+        }
+        assert_eq!(solver.get().err(), Some(SolveError::TablesFailed));
+
+        solver.retry();
+        assert!(matches!(solver.state, State::Generating(_)));
+    }
+
+    #[test]
+    fn solve() {
+        let solver = Solver::new();
+
+        let mut stickers = Stickers::new();
+        assert!(solver.solve(&stickers).unwrap().is_empty());
+
+        let scramble: Algorithm = "D2 F' U2 B2 L2 D2 F' L2 F U2 F L' D' U2 R' D2 L B' U F2 R"
+            .parse()
+            .unwrap();
+        scramble.apply_stickers(&mut stickers);
+
+        let solution = solver.solve(&stickers).unwrap();
+        assert!(solution.moves().len() <= DEFAULT_MAX_LENGTH);
+        solution.apply_stickers(&mut stickers);
+        assert!(stickers.solved());
+    }
+}
@@ -0,0 +1,142 @@
+use crate::components::algorithm::{Algorithm, Move};
+use crate::components::cubie::CubieCube;
+use crate::solver::tables::{N_FLIP, N_TWIST};
+use crate::solver::{to_move, SolveError, Solver};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{FromEntropy, Rng, SeedableRng};
+
+/// Scrambles shorter than this are rejected, matching the WCA rule that a
+/// scramble must need at least two moves to solve.
+const MIN_SOLUTION_LENGTH: usize = 2;
+
+pub struct Scrambler {
+    rng: StdRng,
+}
+
+impl Scrambler {
+    pub fn new() -> Scrambler {
+        Scrambler {
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Creates a scrambler that always produces the same scrambles for the
+    /// same seed.
+    pub fn from_seed(seed: u64) -> Scrambler {
+        Scrambler {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Picks a state uniformly from every state reachable from solved.
+    pub fn random_state(&mut self) -> CubieCube {
+        let mut cube = CubieCube::SOLVED;
+
+        cube.cp.shuffle(&mut self.rng);
+        cube.ep.shuffle(&mut self.rng);
+        cube.set_twist(self.rng.gen_range(0, N_TWIST));
+        cube.set_flip(self.rng.gen_range(0, N_FLIP));
+
+        // swapping two edges pairs every odd permutation with an even one so
+        // the result stays uniform
+        if !cube.is_solvable() {
+            cube.ep.swap(0, 1);
+        }
+
+        cube
+    }
+
+    /// Generates a scramble reaching a uniformly random state, the way WCA
+    /// scrambles are made.
+    pub fn random_state_scramble(&mut self, solver: &Solver) -> Result<Algorithm, SolveError> {
+        loop {
+            let state = self.random_state();
+            let solution = solver.solve(&state.to_stickers())?;
+            if solution.moves().len() >= MIN_SOLUTION_LENGTH {
+                return Ok(solution.inverse());
+            }
+        }
+    }
+
+    /// Generates `length` random turns for an NxN cube, using wide turns of
+    /// up to half the cube on bigger cubes. Consecutive turns never share an
+    /// axis unless they are on opposite faces, and never more than two in a
+    /// row, so no turn cancels or merges with an earlier one.
+    pub fn random_moves(&mut self, size: usize, length: usize) -> Algorithm {
+        let mut axes: Vec<usize> = Vec::with_capacity(length);
+
+        while axes.len() < length {
+            let axis = self.rng.gen_range(0, 6);
+            let redundant = match axes[..] {
+                [.., prev, last] => axis == last || (axis % 3 == last % 3 && axis % 3 == prev % 3),
+                [last] => axis == last,
+                [] => false,
+            };
+            if !redundant {
+                axes.push(axis);
+            }
+        }
+
+        let max_depth = size / 2;
+        axes.into_iter()
+            .map(|axis| {
+                let mv = to_move(axis * 3 + self.rng.gen_range(0, 3));
+                if max_depth > 1 {
+                    let depth = self.rng.gen_range(1, max_depth + 1);
+                    Move { with: true, ..mv }.at_depth(depth)
+                } else {
+                    mv
+                }
+            })
+            .collect::<Vec<Move>>()
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::stickers::Stickers;
+
+    #[test]
+    fn seeded() {
+        let a = Scrambler::from_seed(7).random_moves(3, 25);
+        let b = Scrambler::from_seed(7).random_moves(3, 25);
+        assert_eq!(a, b);
+        assert_eq!(a.moves().len(), 25);
+
+        let moves = a.moves();
+        for w in moves.windows(2) {
+            assert_ne!(w[0].turn, w[1].turn);
+        }
+        for w in moves.windows(3) {
+            let axes: Vec<_> = w.iter().map(|m| m.turn.axis().abs()).collect();
+            assert!(!(axes[0] == axes[1] && axes[1] == axes[2]));
+        }
+
+        let big = Scrambler::from_seed(7).random_moves(6, 60);
+        assert!(big.moves().iter().all(|m| m.depth <= 3));
+        assert!(big.moves().iter().any(|m| m.depth == 3 && m.with));
+    }
+
+    #[test]
+    fn random_state() {
+        let solver = Solver::new();
+        let mut scrambler = Scrambler::from_seed(42);
+
+        for _ in 0..5 {
+            let scramble = scrambler.random_state_scramble(&solver).unwrap();
+            let mut stickers = Stickers::new();
+            scramble.apply_stickers(&mut stickers);
+            assert!(stickers.validate().is_ok());
+            assert!(!stickers.solved());
+        }
+
+        let mut scrambler = Scrambler::from_seed(42);
+        for _ in 0..1000 {
+            assert!(scrambler.random_state().is_solvable());
+        }
+    }
+}
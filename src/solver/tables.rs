@@ -0,0 +1,338 @@
+use crate::components::cubie::{CubieCube, MOVES};
+
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+pub const N_MOVES: usize = 18;
+pub const N_TWIST: usize = 2187;
+pub const N_FLIP: usize = 2048;
+pub const N_SLICE: usize = 495;
+pub const N_PERM8: usize = 40320;
+pub const N_SLICE_PERM: usize = 24;
+
+/// Moves that keep the cube in the phase 2 subgroup: U, U2, U', D, D2, D',
+/// R2, L2, F2 and B2.
+pub static PHASE2_MOVES: [usize; 10] = [0, 1, 2, 9, 10, 11, 4, 13, 7, 16];
+
+const MOVE_FILE: &str = "twophase.move";
+const PRUN_FILE: &str = "twophase.prun";
+const MOVE_MAGIC: &[u8; 8] = b"CUBEMOVE";
+const PRUN_MAGIC: &[u8; 8] = b"CUBEPRUN";
+const CACHE_VERSION: u32 = 1;
+const UNSEEN: u8 = 0xff;
+
+pub struct Tables {
+    pub twist_move: Vec<u16>,
+    pub flip_move: Vec<u16>,
+    pub slice_move: Vec<u16>,
+    pub corner_move: Vec<u16>,
+    pub edge8_move: Vec<u16>,
+    pub slice_perm_move: Vec<u16>,
+
+    pub slice_twist_prun: Vec<u8>,
+    pub slice_flip_prun: Vec<u8>,
+    pub corner_slice_prun: Vec<u8>,
+    pub edge8_slice_prun: Vec<u8>,
+}
+
+fn move_table<G, S>(size: usize, moves: &[usize], get: G, set: S) -> Vec<u16>
+where
+    G: Fn(&CubieCube) -> usize,
+    S: Fn(&mut CubieCube, usize),
+{
+    let mut table = vec![0; size * N_MOVES];
+
+    for coord in 0..size {
+        let mut cube = CubieCube::SOLVED;
+        set(&mut cube, coord);
+
+        for (axis, mv) in MOVES.iter().enumerate() {
+            for power in 0..3 {
+                cube = cube.multiply(mv);
+                let m = axis * 3 + power;
+                if moves.contains(&m) {
+                    table[coord * N_MOVES + m] = get(&cube) as u16;
+                }
+            }
+            // a fourth turn brings the cube back for the next axis
+            cube = cube.multiply(mv);
+        }
+    }
+
+    table
+}
+
+// breadth first search over the product of two coordinates, storing the
+// distance to the solved state for every pair
+fn prune_table(
+    moves: &[usize],
+    a_move: &[u16],
+    a_size: usize,
+    b_move: &[u16],
+    b_size: usize,
+) -> Vec<u8> {
+    let mut table = vec![UNSEEN; a_size * b_size];
+    table[0] = 0;
+    let mut filled = 1;
+    let mut depth = 0;
+
+    while filled < table.len() {
+        for index in 0..table.len() {
+            if table[index] != depth {
+                continue;
+            }
+
+            let a = index / b_size;
+            let b = index % b_size;
+            for &m in moves {
+                let next =
+                    a_move[a * N_MOVES + m] as usize * b_size + b_move[b * N_MOVES + m] as usize;
+                if table[next] == UNSEEN {
+                    table[next] = depth + 1;
+                    filled += 1;
+                }
+            }
+        }
+        depth += 1;
+    }
+
+    table
+}
+
+impl Tables {
+    /// Loads the move and pruning tables from files in `cache_dir`,
+    /// generating and saving whichever are missing or stale.
+    pub fn load(cache_dir: Option<&Path>) -> Tables {
+        let move_cache = cache_dir.map(|dir| dir.join(MOVE_FILE));
+        let prun_cache = cache_dir.map(|dir| dir.join(PRUN_FILE));
+
+        let mut tables = Tables {
+            twist_move: Vec::new(),
+            flip_move: Vec::new(),
+            slice_move: Vec::new(),
+            corner_move: Vec::new(),
+            edge8_move: Vec::new(),
+            slice_perm_move: Vec::new(),
+            slice_twist_prun: Vec::new(),
+            slice_flip_prun: Vec::new(),
+            corner_slice_prun: Vec::new(),
+            edge8_slice_prun: Vec::new(),
+        };
+
+        if move_cache
+            .as_deref()
+            .is_none_or(|path| tables.read_moves(path).is_err())
+        {
+            tables.generate_moves();
+            if let Some(path) = &move_cache {
+                if let Err(e) = tables.write_moves(path) {
+                    eprintln!("failed to write {}: {}", path.display(), e);
+                }
+            }
+        }
+
+        if prun_cache
+            .as_deref()
+            .is_none_or(|path| tables.read_pruning(path).is_err())
+        {
+            tables.generate_pruning();
+            if let Some(path) = &prun_cache {
+                if let Err(e) = tables.write_pruning(path) {
+                    eprintln!("failed to write {}: {}", path.display(), e);
+                }
+            }
+        }
+
+        tables
+    }
+
+    fn generate_moves(&mut self) {
+        let all_moves = (0..N_MOVES).collect::<Vec<_>>();
+
+        self.twist_move = move_table(N_TWIST, &all_moves, CubieCube::twist, CubieCube::set_twist);
+        self.flip_move = move_table(N_FLIP, &all_moves, CubieCube::flip, CubieCube::set_flip);
+        self.slice_move = move_table(N_SLICE, &all_moves, CubieCube::slice, CubieCube::set_slice);
+        self.corner_move = move_table(
+            N_PERM8,
+            &all_moves,
+            CubieCube::corner_perm,
+            CubieCube::set_corner_perm,
+        );
+        self.edge8_move = move_table(
+            N_PERM8,
+            &PHASE2_MOVES,
+            CubieCube::edge8_perm,
+            CubieCube::set_edge8_perm,
+        );
+        self.slice_perm_move = move_table(
+            N_SLICE_PERM,
+            &PHASE2_MOVES,
+            CubieCube::slice_perm,
+            CubieCube::set_slice_perm,
+        );
+    }
+
+    fn generate_pruning(&mut self) {
+        self.slice_twist_prun = prune_table(
+            &(0..N_MOVES).collect::<Vec<_>>(),
+            &self.slice_move,
+            N_SLICE,
+            &self.twist_move,
+            N_TWIST,
+        );
+        self.slice_flip_prun = prune_table(
+            &(0..N_MOVES).collect::<Vec<_>>(),
+            &self.slice_move,
+            N_SLICE,
+            &self.flip_move,
+            N_FLIP,
+        );
+        self.corner_slice_prun = prune_table(
+            &PHASE2_MOVES,
+            &self.corner_move,
+            N_PERM8,
+            &self.slice_perm_move,
+            N_SLICE_PERM,
+        );
+        self.edge8_slice_prun = prune_table(
+            &PHASE2_MOVES,
+            &self.edge8_move,
+            N_PERM8,
+            &self.slice_perm_move,
+            N_SLICE_PERM,
+        );
+    }
+
+    fn read_moves(&mut self, path: &Path) -> io::Result<()> {
+        // each entry is a little endian u16
+        let sizes = [
+            N_TWIST * N_MOVES * 2,
+            N_FLIP * N_MOVES * 2,
+            N_SLICE * N_MOVES * 2,
+            N_PERM8 * N_MOVES * 2,
+            N_PERM8 * N_MOVES * 2,
+            N_SLICE_PERM * N_MOVES * 2,
+        ];
+        let mut tables = read_cache(path, MOVE_MAGIC, &sizes)?
+            .into_iter()
+            .map(|table| {
+                table
+                    .chunks_exact(2)
+                    .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                    .collect()
+            });
+
+        self.twist_move = tables.next().unwrap();
+        self.flip_move = tables.next().unwrap();
+        self.slice_move = tables.next().unwrap();
+        self.corner_move = tables.next().unwrap();
+        self.edge8_move = tables.next().unwrap();
+        self.slice_perm_move = tables.next().unwrap();
+        Ok(())
+    }
+
+    fn write_moves(&self, path: &Path) -> io::Result<()> {
+        let tables = [
+            &self.twist_move,
+            &self.flip_move,
+            &self.slice_move,
+            &self.corner_move,
+            &self.edge8_move,
+            &self.slice_perm_move,
+        ];
+        let bytes = tables
+            .iter()
+            .map(|table| table.iter().flat_map(|v| v.to_le_bytes()).collect())
+            .collect::<Vec<Vec<u8>>>();
+        write_cache(path, MOVE_MAGIC, &bytes)
+    }
+
+    fn read_pruning(&mut self, path: &Path) -> io::Result<()> {
+        let sizes = [
+            N_SLICE * N_TWIST,
+            N_SLICE * N_FLIP,
+            N_PERM8 * N_SLICE_PERM,
+            N_PERM8 * N_SLICE_PERM,
+        ];
+        let mut tables = read_cache(path, PRUN_MAGIC, &sizes)?.into_iter();
+
+        self.slice_twist_prun = tables.next().unwrap();
+        self.slice_flip_prun = tables.next().unwrap();
+        self.corner_slice_prun = tables.next().unwrap();
+        self.edge8_slice_prun = tables.next().unwrap();
+        Ok(())
+    }
+
+    fn write_pruning(&self, path: &Path) -> io::Result<()> {
+        write_cache(
+            path,
+            PRUN_MAGIC,
+            &[
+                &self.slice_twist_prun,
+                &self.slice_flip_prun,
+                &self.corner_slice_prun,
+                &self.edge8_slice_prun,
+            ],
+        )
+    }
+}
+
+// reads tables of the given sizes in bytes, written by `write_cache` with
+// the same magic
+fn read_cache(path: &Path, magic: &[u8; 8], sizes: &[usize]) -> io::Result<Vec<Vec<u8>>> {
+    let mut data = Vec::new();
+    File::open(path)?.read_to_end(&mut data)?;
+
+    let header = magic.len() + 4;
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid solver table cache");
+
+    if data.len() != header + sizes.iter().sum::<usize>()
+        || &data[..magic.len()] != magic
+        || data[magic.len()..header] != CACHE_VERSION.to_le_bytes()
+    {
+        return Err(invalid());
+    }
+
+    let mut rest = &data[header..];
+    let mut tables = Vec::with_capacity(sizes.len());
+    for &size in sizes {
+        let (table, next) = rest.split_at(size);
+        tables.push(table.to_vec());
+        rest = next;
+    }
+    Ok(tables)
+}
+
+fn write_cache<T: AsRef<[u8]>>(path: &Path, magic: &[u8; 8], tables: &[T]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut file = File::create(path)?;
+    file.write_all(magic)?;
+    file.write_all(&CACHE_VERSION.to_le_bytes())?;
+    for table in tables {
+        file.write_all(table.as_ref())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_round_trip() {
+        let dir = std::env::temp_dir().join(format!("cube-tables-{}", std::process::id()));
+        let generated = Tables::load(Some(&dir));
+        let cached = Tables::load(Some(&dir));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(cached.twist_move, generated.twist_move);
+        assert_eq!(cached.corner_move, generated.corner_move);
+        assert_eq!(cached.slice_perm_move, generated.slice_perm_move);
+        assert_eq!(cached.slice_flip_prun, generated.slice_flip_prun);
+        assert_eq!(cached.edge8_slice_prun, generated.edge8_slice_prun);
+    }
+}
@@ -0,0 +1,238 @@
+//! Import and export of csTimer's JSON export files.
+//!
+//! An export holds one `sessionN` array per session plus a `properties`
+//! object whose `sessionData` is a JSON string naming the sessions. Each solve
+//! is `[[penalty, time], scramble, comment, timestamp, ...]` where the time is
+//! in milliseconds without the penalty and the penalty is 0, 2000 for +2 or -1
+//! for a DNF.
+
+use crate::timer::history::Entry;
+use crate::timer::Penalty;
+
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum CsTimerError {
+    Json(serde_json::Error),
+    /// Valid JSON that is not laid out like a csTimer export.
+    Format(String),
+}
+
+impl fmt::Display for CsTimerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CsTimerError::Json(e) => write!(f, "invalid JSON: {}", e),
+            CsTimerError::Format(e) => write!(f, "not a csTimer export: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CsTimerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CsTimerError::Json(e) => Some(e),
+            CsTimerError::Format(_) => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for CsTimerError {
+    fn from(e: serde_json::Error) -> CsTimerError {
+        CsTimerError::Json(e)
+    }
+}
+
+fn format_error<T>(msg: String) -> Result<T, CsTimerError> {
+    Err(CsTimerError::Format(msg))
+}
+
+// the session names keyed by number, from the string in `sessionData`
+fn session_names(root: &Map<String, Value>) -> Result<Map<String, Value>, CsTimerError> {
+    let data = root
+        .get("properties")
+        .and_then(|p| p.get("sessionData"))
+        .and_then(Value::as_str);
+
+    match data {
+        Some(data) => match serde_json::from_str(data)? {
+            Value::Object(names) => Ok(names),
+            _ => format_error("sessionData is not an object".to_string()),
+        },
+        None => Ok(Map::new()),
+    }
+}
+
+fn parse_solve(solve: &Value, session: &str) -> Option<Entry> {
+    let solve = solve.as_array()?;
+    let result = solve.first()?.as_array()?;
+
+    let penalty = match result.first()?.as_i64()? {
+        0 => Penalty::Ok,
+        2000 => Penalty::PlusTwo,
+        -1 => Penalty::Dnf,
+        _ => return None,
+    };
+
+    Some(Entry {
+        scramble: solve.get(1)?.as_str()?.to_string(),
+        time: result.get(1)?.as_u64()?,
+        penalty,
+        moves: 0,
+        timestamp: solve.get(3)?.as_u64()?,
+        session: session.to_string(),
+        comment: solve.get(2)?.as_str()?.to_string(),
+        reconstruction: String::new(),
+    })
+}
+
+/// Reads every solve from a csTimer export, oldest session first, naming
+/// each session as it was named in csTimer.
+pub fn import(json: &str) -> Result<Vec<Entry>, CsTimerError> {
+    let root = match serde_json::from_str(json)? {
+        Value::Object(root) => root,
+        _ => return format_error("the top level is not an object".to_string()),
+    };
+    let names = session_names(&root)?;
+
+    let mut sessions: Vec<(u64, &Vec<Value>)> = Vec::new();
+    for (key, value) in &root {
+        let number = match key.strip_prefix("session").map(str::parse) {
+            Some(Ok(number)) => number,
+            _ => continue,
+        };
+        match value.as_array() {
+            Some(solves) => sessions.push((number, solves)),
+            None => return format_error(format!("{} is not an array", key)),
+        }
+    }
+    sessions.sort_by_key(|s| s.0);
+
+    let mut entries = Vec::new();
+    for (number, solves) in sessions {
+        let name = names
+            .get(&number.to_string())
+            .and_then(|s| s.get("name"))
+            .and_then(|name| match name {
+                Value::String(name) => Some(name.clone()),
+                Value::Number(name) => Some(name.to_string()),
+                _ => None,
+            })
+            .unwrap_or_else(|| format!("Session {}", number));
+
+        for (i, solve) in solves.iter().enumerate() {
+            match parse_solve(solve, &name) {
+                Some(entry) => entries.push(entry),
+                None => return format_error(format!("solve {} of session{}", i + 1, number)),
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Writes solves as a csTimer export with one csTimer session for each of
+/// the history's sessions.
+pub fn export(entries: &[Entry]) -> String {
+    let mut sessions: Vec<&str> = Vec::new();
+    for entry in entries {
+        if !sessions.contains(&entry.session.as_str()) {
+            sessions.push(&entry.session);
+        }
+    }
+
+    let mut root = Map::new();
+    let mut names = Map::new();
+    for (i, &session) in sessions.iter().enumerate() {
+        let number = i + 1;
+        let solves = entries
+            .iter()
+            .filter(|e| e.session == session)
+            .map(|e| {
+                let penalty = match e.penalty {
+                    Penalty::Ok => 0,
+                    Penalty::PlusTwo => 2000,
+                    Penalty::Dnf => -1,
+                };
+                json!([[penalty, e.time], e.scramble, e.comment, e.timestamp])
+            })
+            .collect();
+
+        root.insert(format!("session{}", number), Value::Array(solves));
+        names.insert(
+            number.to_string(),
+            json!({ "name": session, "opt": {}, "rank": number }),
+        );
+    }
+
+    let names = Value::Object(names).to_string();
+    root.insert("properties".to_string(), json!({ "sessionData": names }));
+    Value::Object(root).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::history::History;
+
+    static EXPORT: &str = include_str!("../../tests/fixtures/cstimer.json");
+    static FULL_EXPORT: &str = include_str!("../../tests/fixtures/cstimer_full.json");
+
+    #[test]
+    fn round_trip() {
+        let entries = import(EXPORT).unwrap();
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[0].session, "3x3");
+        assert_eq!(entries[0].time, 12345);
+        assert_eq!(entries[0].scramble, "R U R' U' F2 D L2 B");
+        assert_eq!(entries[1].penalty, Penalty::PlusTwo);
+        assert_eq!(entries[1].comment, "lockup");
+        assert_eq!(entries[2].penalty, Penalty::Dnf);
+        assert_eq!(entries[3].session, "OH");
+
+        // the export is exactly what was imported
+        let exported: Value = serde_json::from_str(&export(&entries)).unwrap();
+        let fixture: Value = serde_json::from_str(EXPORT).unwrap();
+        assert_eq!(exported["session1"], fixture["session1"]);
+        assert_eq!(exported["session2"], fixture["session2"]);
+        assert_eq!(import(&export(&entries)).unwrap(), entries);
+    }
+
+    #[test]
+    fn full_export() {
+        // exports from csTimer itself carry more properties, extra fields on
+        // solves and sessions without names or solves
+        let entries = import(FULL_EXPORT).unwrap();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].session, "main");
+        assert_eq!(entries[1].scramble, "");
+        assert_eq!(entries[2].session, "Session 3");
+        assert_eq!(entries[3].session, "10");
+        assert_eq!(import(&export(&entries)).unwrap(), entries);
+
+        assert!(matches!(import("[]"), Err(CsTimerError::Format(_))));
+        assert!(matches!(import("{"), Err(CsTimerError::Json(_))));
+        assert!(matches!(
+            import(r#"{"session1": [[[5, 100], "", "", 0]]}"#),
+            Err(CsTimerError::Format(_))
+        ));
+    }
+
+    #[test]
+    fn no_duplicates() {
+        let path = std::env::temp_dir().join(format!("cube-cstimer-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut history = History::open(&path).unwrap();
+        assert_eq!(history.merge(import(EXPORT).unwrap()).unwrap(), 5);
+        assert_eq!(history.merge(import(EXPORT).unwrap()).unwrap(), 0);
+        assert_eq!(history.merge(import(FULL_EXPORT).unwrap()).unwrap(), 4);
+
+        let history = History::open(&path).unwrap();
+        assert_eq!(history.entries().len(), 9);
+        assert_eq!(history.sessions(), ["3x3", "OH", "main", "Session 3", "10"]);
+        assert_eq!(history.session_stats("OH").count, 2);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
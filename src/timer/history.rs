@@ -0,0 +1,339 @@
+use crate::timer::replay;
+use crate::timer::stats::{average_of, SolveTime, Stats};
+use crate::timer::{format_time, Penalty, Solve};
+use crate::util;
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+const HISTORY_FILE: &str = "solves.jsonl";
+/// The session solves made in the app are recorded in.
+pub const DEFAULT_SESSION: &str = "default";
+
+fn default_session() -> String {
+    DEFAULT_SESSION.to_string()
+}
+
+/// A finished solve as it is stored on disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub scramble: String,
+    /// The time without the penalty in milliseconds.
+    pub time: u64,
+    pub penalty: Penalty,
+    /// Turns made after inspection, not counting whole cube rotations. 0 when
+    /// not known, as for imported solves.
+    pub moves: usize,
+    /// Seconds since the Unix epoch when the solve was finished.
+    pub timestamp: u64,
+    #[serde(default = "default_session")]
+    pub session: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub comment: String,
+    /// Every move from the start of inspection in the format of
+    /// `replay::format_log`, empty when the moves were not recorded.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub reconstruction: String,
+}
+
+impl Entry {
+    pub fn from_solve(solve: &Solve) -> Entry {
+        Entry {
+            scramble: solve.scramble.to_string(),
+            time: solve.time.as_millis() as u64,
+            penalty: solve.penalty,
+            moves: solve
+                .solution()
+                .filter(|m| !m.mv.turn.is_rotation())
+                .count(),
+            timestamp: solve
+                .date
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            session: default_session(),
+            comment: String::new(),
+            reconstruction: replay::format_log(&solve.moves),
+        }
+    }
+
+    // two entries are the same solve if they were finished at the same
+    // moment with the same result, wherever they were imported from
+    fn same_solve(&self, other: &Entry) -> bool {
+        self.timestamp == other.timestamp
+            && self.time == other.time
+            && self.penalty == other.penalty
+            && self.scramble == other.scramble
+    }
+
+    pub fn raw_time(&self) -> Duration {
+        Duration::from_millis(self.time)
+    }
+
+    pub fn result(&self) -> SolveTime {
+        match self.penalty {
+            Penalty::Ok => SolveTime::Time(self.raw_time()),
+            Penalty::PlusTwo => SolveTime::Time(self.raw_time() + Duration::from_secs(2)),
+            Penalty::Dnf => SolveTime::Dnf,
+        }
+    }
+}
+
+/// Every recorded solve, kept in a JSON Lines file with one entry per line
+/// so new solves can be appended without rewriting it.
+pub struct History {
+    path: PathBuf,
+    entries: Vec<Entry>,
+}
+
+impl History {
+    /// Opens the history in the user's data directory.
+    pub fn open_default() -> io::Result<History> {
+        let dir = util::data_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+        History::open(dir.join(HISTORY_FILE))
+    }
+
+    /// Loads the history from `path`. A missing file is an empty history.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<History> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = Vec::new();
+
+        let file = match File::open(&path) {
+            Ok(file) => Some(file),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        if let Some(file) = file {
+            for (i, line) in BufReader::new(file).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry = serde_json::from_str(&line).map_err(|e| {
+                    let msg = format!("{}:{}: {}", path.display(), i + 1, e);
+                    io::Error::new(io::ErrorKind::InvalidData, msg)
+                })?;
+                entries.push(entry);
+            }
+        }
+
+        Ok(History { path, entries })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Appends an entry to the file and the history.
+    pub fn add(&mut self, entry: Entry) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Adds the entries that are not already in the history, returning how
+    /// many were new.
+    pub fn merge(&mut self, entries: Vec<Entry>) -> io::Result<usize> {
+        let mut added = 0;
+        for entry in entries {
+            if !self.entries.iter().any(|e| e.same_solve(&entry)) {
+                self.add(entry)?;
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    /// The names of every session in the order they were first used.
+    pub fn sessions(&self) -> Vec<&str> {
+        let mut sessions: Vec<&str> = Vec::new();
+        for entry in &self.entries {
+            if !sessions.contains(&entry.session.as_str()) {
+                sessions.push(&entry.session);
+            }
+        }
+        sessions
+    }
+
+    /// The statistics over every solve in the history.
+    pub fn stats(&self) -> Stats {
+        self.stats_where(|_| true)
+    }
+
+    pub fn session_stats(&self, session: &str) -> Stats {
+        self.stats_where(|e| e.session == session)
+    }
+
+    fn stats_where<F: Fn(&Entry) -> bool>(&self, f: F) -> Stats {
+        let solves: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|e| f(e))
+            .map(|e| (e.result(), e.raw_time(), e.moves))
+            .collect();
+        Stats::new(&solves)
+    }
+
+    /// Writes every solve as CSV along with the averages of 5 and 12 ending
+    /// at it.
+    pub fn write_csv<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(
+            w,
+            "No.,Time,Penalty,Result,ao5,ao12,Moves,TPS,Scramble,Date,Session,Comment"
+        )?;
+
+        let results: Vec<_> = self.entries.iter().map(Entry::result).collect();
+        for (i, entry) in self.entries.iter().enumerate() {
+            let average = |n: usize| {
+                if i + 1 >= n {
+                    average_of(&results[i + 1 - n..=i]).to_string()
+                } else {
+                    String::new()
+                }
+            };
+            let (moves, tps) = if entry.time > 0 && entry.moves > 0 {
+                let tps = entry.moves as f64 * 1000.0 / entry.time as f64;
+                (entry.moves.to_string(), format!("{:.2}", tps))
+            } else {
+                (String::new(), String::new())
+            };
+
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{},{},{},{},{},{}",
+                i + 1,
+                format_time(entry.raw_time()),
+                entry.penalty,
+                results[i],
+                average(5),
+                average(12),
+                moves,
+                tps,
+                csv_field(&entry.scramble),
+                format_date(entry.timestamp),
+                csv_field(&entry.session),
+                csv_field(&entry.comment),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Formats seconds since the Unix epoch as an ISO 8601 UTC date and time.
+pub fn format_date(timestamp: u64) -> String {
+    let (days, secs) = (timestamp / 86400, timestamp % 86400);
+
+    // converts days since 1970-01-01 to a civil date, counting eras of 400
+    // years from 0000-03-01 so leap days fall at the end of each year
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(time: u64, penalty: Penalty) -> Entry {
+        Entry {
+            scramble: "R U R' U'".to_string(),
+            time,
+            penalty,
+            moves: 40,
+            timestamp: 1_600_000_000,
+            session: default_session(),
+            comment: String::new(),
+            reconstruction: "R@6000 U@6200".to_string(),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = std::env::temp_dir().join(format!("cube-history-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut history = History::open(&path).unwrap();
+        assert!(history.entries().is_empty());
+        history.add(entry(10000, Penalty::Ok)).unwrap();
+        history.add(entry(12500, Penalty::PlusTwo)).unwrap();
+        history.add(entry(9000, Penalty::Dnf)).unwrap();
+
+        let line = fs::read_to_string(&path).unwrap();
+        assert!(
+            line.starts_with(r#"{"scramble":"R U R' U'","time":10000,"penalty":"OK","moves":40,"#)
+        );
+
+        let history = History::open(&path).unwrap();
+        assert_eq!(history.entries().len(), 3);
+        assert_eq!(history.entries()[1], entry(12500, Penalty::PlusTwo));
+        assert_eq!(
+            history.stats().best,
+            Some(SolveTime::Time(Duration::from_secs(10)))
+        );
+        assert_eq!(history.stats().mo3.unwrap().current, SolveTime::Dnf);
+
+        let mut csv = Vec::new();
+        history.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[2],
+            "2,12.50,+2,14.50,,,40,3.20,R U R' U',2020-09-13T12:26:40Z,default,"
+        );
+        assert!(lines[3].starts_with("3,9.00,DNF,DNF,"));
+
+        fs::write(&path, "{\"scramble\":\n").unwrap();
+        let err = History::open(&path).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(format_date(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_date(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_date(1_700_000_000), "2023-11-14T22:13:20Z");
+    }
+}
@@ -0,0 +1,322 @@
+//! Timing of real solves following the WCA regulations: 15 seconds of
+//! inspection, a +2 penalty for starting within the next 2 seconds and a DNF
+//! after that.
+
+pub mod cstimer;
+pub mod history;
+pub mod replay;
+pub mod stats;
+
+use crate::components::algorithm::{Algorithm, Move};
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime};
+
+pub const INSPECTION: Duration = Duration::from_secs(15);
+// starting later than this after inspection began is a DNF
+const INSPECTION_LIMIT: Duration = Duration::from_secs(17);
+const PLUS_TWO: Duration = Duration::from_secs(2);
+// seconds into inspection at which the solver is warned, as a judge would
+const WARNINGS: [u64; 2] = [8, 12];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Penalty {
+    #[serde(rename = "OK")]
+    Ok,
+    #[serde(rename = "+2")]
+    PlusTwo,
+    #[serde(rename = "DNF")]
+    Dnf,
+}
+
+impl fmt::Display for Penalty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Penalty::Ok => "OK",
+            Penalty::PlusTwo => "+2",
+            Penalty::Dnf => "DNF",
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimedMove {
+    pub mv: Move,
+    /// Time since the recording began, the start of inspection for a
+    /// solve.
+    pub time: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solve {
+    pub scramble: Algorithm,
+    /// Every move made from the start of inspection, including rotations
+    /// during inspection.
+    pub moves: Vec<TimedMove>,
+    /// How long inspection took before the first turn.
+    pub inspection: Duration,
+    /// The time from the first turn until the cube was solved, without any
+    /// penalty.
+    pub time: Duration,
+    pub penalty: Penalty,
+    /// When the solve was finished.
+    pub date: SystemTime,
+}
+
+impl Solve {
+    /// The time with the penalty added, or `None` for a DNF.
+    pub fn result(&self) -> Option<Duration> {
+        match self.penalty {
+            Penalty::Ok => Some(self.time),
+            Penalty::PlusTwo => Some(self.time + PLUS_TWO),
+            Penalty::Dnf => None,
+        }
+    }
+
+    /// The moves made after inspection.
+    pub fn solution(&self) -> impl Iterator<Item = &TimedMove> {
+        let inspection = self.inspection;
+        self.moves.iter().filter(move |m| m.time >= inspection)
+    }
+}
+
+impl fmt::Display for Solve {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.penalty {
+            Penalty::Dnf => write!(f, "DNF({})", format_time(self.time)),
+            Penalty::PlusTwo => write!(f, "{}+", format_time(self.time + PLUS_TWO)),
+            Penalty::Ok => f.write_str(&format_time(self.time)),
+        }
+    }
+}
+
+/// Formats a time as seconds with hundredths, adding minutes once past one.
+pub fn format_time(time: Duration) -> String {
+    let centis = time.as_millis() / 10;
+    let (minutes, centis) = (centis / 6000, centis % 6000);
+    if minutes > 0 {
+        format!("{}:{:02}.{:02}", minutes, centis / 100, centis % 100)
+    } else {
+        format!("{}.{:02}", centis / 100, centis % 100)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum State {
+    Idle,
+    Inspecting { warned: usize },
+    Solving { start: Instant, penalty: Penalty },
+}
+
+/// Tracks a solve from inspection until the cube is solved. Times are passed
+/// in so the timer can be driven by whatever clock the caller uses.
+pub struct Timer {
+    state: State,
+    scramble: Algorithm,
+    inspection_start: Instant,
+    moves: Vec<TimedMove>,
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        Timer {
+            state: State::Idle,
+            scramble: Algorithm::new(),
+            inspection_start: Instant::now(),
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state != State::Idle
+    }
+
+    pub fn is_inspecting(&self) -> bool {
+        matches!(self.state, State::Inspecting { .. })
+    }
+
+    /// Starts inspection of a freshly scrambled cube, abandoning any solve in
+    /// progress.
+    pub fn start_inspection(&mut self, scramble: Algorithm, now: Instant) {
+        self.state = State::Inspecting { warned: 0 };
+        self.scramble = scramble;
+        self.inspection_start = now;
+        self.moves.clear();
+    }
+
+    /// Abandons the current solve without recording it.
+    pub fn cancel(&mut self) {
+        self.state = State::Idle;
+        self.moves.clear();
+    }
+
+    /// Records a move. The solve starts with the first move that is not a
+    /// whole cube rotation, and ends once the move leaves the cube `solved`.
+    pub fn on_move(&mut self, mv: Move, solved: bool, now: Instant) -> Option<Solve> {
+        if self.state == State::Idle {
+            return None;
+        }
+
+        let elapsed = now.saturating_duration_since(self.inspection_start);
+        self.moves.push(TimedMove { mv, time: elapsed });
+
+        if let State::Inspecting { .. } = self.state {
+            if mv.turn.is_rotation() {
+                return None;
+            }
+
+            let penalty = if elapsed <= INSPECTION {
+                Penalty::Ok
+            } else if elapsed <= INSPECTION_LIMIT {
+                Penalty::PlusTwo
+            } else {
+                Penalty::Dnf
+            };
+            self.state = State::Solving {
+                start: now,
+                penalty,
+            };
+        }
+
+        if solved {
+            self.finish(now)
+        } else {
+            None
+        }
+    }
+
+    /// Checks for inspection running out, which ends the solve as a DNF.
+    pub fn update(&mut self, now: Instant) -> Option<Solve> {
+        if self.is_inspecting()
+            && now.saturating_duration_since(self.inspection_start) > INSPECTION_LIMIT
+        {
+            self.state = State::Solving {
+                start: now,
+                penalty: Penalty::Dnf,
+            };
+            return self.finish(now);
+        }
+        None
+    }
+
+    /// Returns the number of seconds of inspection used the first time each
+    /// warning point is passed.
+    pub fn take_warning(&mut self, now: Instant) -> Option<u64> {
+        if let State::Inspecting { warned } = &mut self.state {
+            let elapsed = now.saturating_duration_since(self.inspection_start);
+            match WARNINGS.get(*warned) {
+                Some(&secs) if elapsed >= Duration::from_secs(secs) => {
+                    *warned += 1;
+                    return Some(secs);
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// What a timer display would show: the inspection countdown, the
+    /// running time or nothing when idle.
+    pub fn display(&self, now: Instant) -> String {
+        let elapsed = now.saturating_duration_since(self.inspection_start);
+        match self.state {
+            State::Idle => String::new(),
+            State::Inspecting { .. } if elapsed > INSPECTION => "+2".to_string(),
+            State::Inspecting { .. } => {
+                let left = INSPECTION - elapsed;
+                // count down like a stackmat display, showing 15 at the start
+                let secs = left.as_secs() + if left.subsec_nanos() > 0 { 1 } else { 0 };
+                secs.to_string()
+            }
+            State::Solving { start, .. } => format_time(now.saturating_duration_since(start)),
+        }
+    }
+
+    fn finish(&mut self, now: Instant) -> Option<Solve> {
+        let (start, penalty) = match self.state {
+            State::Solving { start, penalty } => (start, penalty),
+            _ => return None,
+        };
+        self.state = State::Idle;
+
+        Some(Solve {
+            scramble: self.scramble.clone(),
+            moves: std::mem::take(&mut self.moves),
+            inspection: start.saturating_duration_since(self.inspection_start),
+            time: now.saturating_duration_since(start),
+            penalty,
+            date: SystemTime::now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::algorithm::Turn;
+
+    fn secs(s: f32) -> Duration {
+        Duration::from_secs_f32(s)
+    }
+
+    fn mv(turn: Turn) -> Move {
+        Move::new(turn, false, false, false)
+    }
+
+    #[test]
+    fn solve() {
+        let start = Instant::now();
+        let mut timer = Timer::new();
+        assert_eq!(timer.on_move(mv(Turn::Right), true, start), None);
+
+        timer.start_inspection("R".parse().unwrap(), start);
+        assert_eq!(timer.display(start), "15");
+        assert_eq!(timer.display(start + secs(14.5)), "1");
+
+        // rotations during inspection do not start the solve
+        assert_eq!(timer.on_move(mv(Turn::Y), false, start + secs(3.0)), None);
+        assert!(timer.is_inspecting());
+        assert_eq!(timer.take_warning(start + secs(7.0)), None);
+        assert_eq!(timer.take_warning(start + secs(8.5)), Some(8));
+        assert_eq!(timer.take_warning(start + secs(9.0)), None);
+
+        assert_eq!(timer.on_move(mv(Turn::Up), false, start + secs(10.0)), None);
+        assert_eq!(timer.display(start + secs(12.5)), "2.50");
+        let solve = timer
+            .on_move(mv(Turn::Right).inverse(), true, start + secs(14.0))
+            .unwrap();
+
+        assert!(!timer.is_running());
+        assert_eq!(solve.penalty, Penalty::Ok);
+        assert_eq!(solve.inspection, secs(10.0));
+        assert_eq!(solve.result(), Some(secs(4.0)));
+        assert_eq!(solve.moves.len(), 3);
+        assert_eq!(solve.moves[0].time, secs(3.0));
+        assert_eq!(solve.solution().count(), 2);
+        assert_eq!(solve.to_string(), "4.00");
+    }
+
+    #[test]
+    fn penalties() {
+        let start = Instant::now();
+        let mut timer = Timer::new();
+
+        timer.start_inspection(Algorithm::new(), start);
+        assert_eq!(timer.display(start + secs(16.0)), "+2");
+        timer.on_move(mv(Turn::Front), false, start + secs(16.0));
+        let solve = timer
+            .on_move(mv(Turn::Front), true, start + secs(76.5))
+            .unwrap();
+        assert_eq!(solve.penalty, Penalty::PlusTwo);
+        assert_eq!(solve.result(), Some(secs(62.5)));
+        assert_eq!(solve.to_string(), "1:02.50+");
+
+        timer.start_inspection(Algorithm::new(), start);
+        assert_eq!(timer.update(start + secs(16.9)), None);
+        let solve = timer.update(start + secs(17.5)).unwrap();
+        assert_eq!(solve.penalty, Penalty::Dnf);
+        assert_eq!(solve.result(), None);
+        assert!(!timer.is_running());
+    }
+}
@@ -0,0 +1,383 @@
+//! Playing back a recorded solve. The scramble and timestamped move log are
+//! all a replay depends on, so the same log always gives the same states.
+
+use crate::components::algorithm::{Algorithm, Move};
+use crate::components::stickers::Stickers;
+use crate::timer::{Solve, TimedMove};
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+pub const MIN_SPEED: f32 = 0.25;
+pub const MAX_SPEED: f32 = 4.0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogError {
+    /// The position of the bad entry, counting from 1.
+    pub index: usize,
+    pub entry: String,
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid move {} in log: {:?}", self.index, self.entry)
+    }
+}
+
+impl std::error::Error for LogError {}
+
+/// Writes moves as `R@0 U'@350 ...` with times in milliseconds.
+pub fn format_log(moves: &[TimedMove]) -> String {
+    let entries: Vec<_> = moves
+        .iter()
+        .map(|m| format!("{}@{}", m.mv, m.time.as_millis()))
+        .collect();
+    entries.join(" ")
+}
+
+pub fn parse_log(log: &str) -> Result<Vec<TimedMove>, LogError> {
+    log.split_whitespace()
+        .enumerate()
+        .map(|(i, entry)| {
+            let error = || LogError {
+                index: i + 1,
+                entry: entry.to_string(),
+            };
+
+            let at = entry.rfind('@').ok_or_else(error)?;
+            let time = entry[at + 1..].parse().map_err(|_| error())?;
+            let mv = entry[..at].parse().map_err(|_| error())?;
+            Ok(TimedMove {
+                mv,
+                time: Duration::from_millis(time),
+            })
+        })
+        .collect()
+}
+
+/// Every turn made since the cube was scrambled, with the time it was made,
+/// so the cube can be replayed to any point since.
+pub struct TurnLog {
+    scramble: Algorithm,
+    start: Instant,
+    moves: Vec<TimedMove>,
+}
+
+impl TurnLog {
+    /// Starts a log for a cube that `scramble` was just applied to.
+    pub fn new(scramble: Algorithm, now: Instant) -> TurnLog {
+        TurnLog {
+            scramble,
+            start: now,
+            moves: Vec::new(),
+        }
+    }
+
+    /// Continues from where the replay is, as if its moves up to there had
+    /// just been made.
+    pub fn from_replay(replay: &Replay, now: Instant) -> TurnLog {
+        TurnLog {
+            scramble: replay.scramble.clone(),
+            start: now.checked_sub(replay.time).unwrap_or(now),
+            moves: replay.moves[..replay.position].to_vec(),
+        }
+    }
+
+    pub fn moves(&self) -> &[TimedMove] {
+        &self.moves
+    }
+
+    pub fn record(&mut self, mv: Move, now: Instant) {
+        let time = now.saturating_duration_since(self.start);
+        self.moves.push(TimedMove { mv, time });
+    }
+
+    pub fn replay(&self) -> Replay {
+        Replay::new(self.scramble.clone(), self.moves.clone())
+    }
+}
+
+/// A move the cube should make to follow the replay.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReplayStep {
+    Forward(Move),
+    /// Undoes a move, turning it backwards.
+    Back(Move),
+}
+
+impl ReplayStep {
+    /// The move to make on the cube.
+    pub fn apply(self) -> Move {
+        match self {
+            ReplayStep::Forward(mv) => mv,
+            ReplayStep::Back(mv) => mv.inverse(),
+        }
+    }
+}
+
+pub struct Replay {
+    scramble: Algorithm,
+    moves: Vec<TimedMove>,
+    // how many moves have been made
+    position: usize,
+    // the time of the playhead on the clock the moves were recorded with
+    time: Duration,
+    speed: f32,
+    paused: bool,
+}
+
+impl Replay {
+    /// Creates a paused replay positioned before the first move.
+    pub fn new(scramble: Algorithm, moves: Vec<TimedMove>) -> Replay {
+        Replay {
+            scramble,
+            moves,
+            position: 0,
+            time: Duration::from_secs(0),
+            speed: 1.0,
+            paused: true,
+        }
+    }
+
+    pub fn from_solve(solve: &Solve) -> Replay {
+        Replay::new(solve.scramble.clone(), solve.moves.clone())
+    }
+
+    pub fn scramble(&self) -> &Algorithm {
+        &self.scramble
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The time of the playhead since the recording started.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// The moves that take a solved cube to the state after the first
+    /// `position` moves, starting with the scramble.
+    pub fn moves_until(&self, position: usize) -> Vec<Move> {
+        let mut moves = self.scramble.moves();
+        let position = position.min(self.moves.len());
+        moves.extend(self.moves[..position].iter().map(|m| m.mv));
+        moves
+    }
+
+    /// The state of the cube after the first `position` moves.
+    pub fn stickers_at(&self, size: usize, position: usize) -> Stickers {
+        let mut stickers = Stickers::with_size(size);
+        for mv in self.moves_until(position) {
+            mv.apply_stickers(&mut stickers);
+        }
+        stickers
+    }
+
+    /// Moves the playhead forward by `dt` of wall clock time at the current
+    /// speed, returning the moves that were passed.
+    pub fn advance(&mut self, dt: Duration) -> Vec<ReplayStep> {
+        if self.paused {
+            return Vec::new();
+        }
+        self.time += dt.mul_f64(f64::from(self.speed));
+
+        let mut steps = Vec::new();
+        while let Some(m) = self.moves.get(self.position) {
+            if m.time > self.time {
+                break;
+            }
+            steps.push(ReplayStep::Forward(m.mv));
+            self.position += 1;
+        }
+
+        if self.position == self.moves.len() {
+            self.paused = true;
+        }
+        steps
+    }
+
+    pub fn step_forward(&mut self) -> Option<ReplayStep> {
+        let m = *self.moves.get(self.position)?;
+        self.position += 1;
+        self.time = m.time;
+        Some(ReplayStep::Forward(m.mv))
+    }
+
+    pub fn step_back(&mut self) -> Option<ReplayStep> {
+        if self.position == 0 {
+            return None;
+        }
+        self.position -= 1;
+        let m = self.moves[self.position];
+        self.time = match self.position {
+            0 => Duration::from_secs(0),
+            i => self.moves[i - 1].time,
+        };
+        Some(ReplayStep::Back(m.mv))
+    }
+
+    /// Jumps to just after the first `position` moves. The cube has to be
+    /// reset and given `moves_until(position)` rather than turned there.
+    pub fn seek(&mut self, position: usize) {
+        self.position = position.min(self.moves.len());
+        self.time = match self.position {
+            0 => Duration::from_secs(0),
+            i => self.moves[i - 1].time,
+        };
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "move {}/{} {:.2}s {}x",
+            self.position,
+            self.moves.len(),
+            self.time.as_secs_f32(),
+            self.speed
+        )?;
+        if self.paused {
+            f.write_str(" paused")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static LOG: &str = "y@1200 R@5000 U@5150 R'@5300 U'@5480 Rw2@6000 3Rw'@6400";
+
+    fn replay() -> Replay {
+        let moves = parse_log("R@0 U@250 R'@600 U'@1000").unwrap();
+        let scramble = "U R U' R'".parse().unwrap();
+        Replay::new(scramble, moves)
+    }
+
+    #[test]
+    fn log() {
+        let moves = parse_log(LOG).unwrap();
+        assert_eq!(moves.len(), 7);
+        assert_eq!(moves[1].time, Duration::from_millis(5000));
+        assert_eq!(moves[6].mv.depth, 3);
+        assert_eq!(format_log(&moves), LOG);
+
+        let err = parse_log("R@0 U@x").unwrap_err();
+        assert_eq!(err.index, 2);
+        assert!(parse_log("R").is_err());
+        assert!(parse_log("(R U)@5").is_err());
+    }
+
+    #[test]
+    fn playback() {
+        let mut replay = replay();
+        assert!(replay.advance(Duration::from_millis(100)).is_empty());
+
+        replay.set_paused(false);
+        replay.set_speed(10.0);
+        assert_eq!(replay.speed(), MAX_SPEED);
+        replay.set_speed(0.5);
+
+        let steps = replay.advance(Duration::from_millis(600));
+        assert_eq!(steps.len(), 2);
+        assert_eq!(replay.position(), 2);
+        assert!((replay.time().as_secs_f64() - 0.3).abs() < 1.0e-6);
+
+        let steps = replay.advance(Duration::from_secs(10));
+        assert_eq!(steps.len(), 2);
+        assert!(replay.is_paused());
+        assert!(replay.stickers_at(3, replay.position()).solved());
+        assert_eq!(replay.to_string(), "move 4/4 5.30s 0.5x paused");
+    }
+
+    #[test]
+    fn deterministic() {
+        // stepping and seeking always lands on the same states
+        let mut replay = replay();
+        let mut stickers = replay.stickers_at(3, 0);
+        assert!(!stickers.solved());
+
+        while let Some(step) = replay.step_forward() {
+            step.apply().apply_stickers(&mut stickers);
+            let expected = replay.stickers_at(3, replay.position());
+            assert_eq!(format!("{:?}", stickers), format!("{:?}", expected));
+        }
+        assert!(stickers.solved());
+
+        replay
+            .step_back()
+            .unwrap()
+            .apply()
+            .apply_stickers(&mut stickers);
+        replay
+            .step_back()
+            .unwrap()
+            .apply()
+            .apply_stickers(&mut stickers);
+        assert_eq!(replay.position(), 2);
+        assert_eq!(replay.time(), Duration::from_millis(250));
+        assert_eq!(
+            format!("{:?}", stickers),
+            format!("{:?}", replay.stickers_at(3, 2))
+        );
+
+        replay.seek(0);
+        assert_eq!(replay.step_back(), None);
+        assert_eq!(replay.time(), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn turn_log() {
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+        let scramble: Algorithm = "R U R' U'".parse().unwrap();
+
+        let mut log = TurnLog::new(scramble.clone(), start);
+        let mut stickers = Stickers::new();
+        scramble.apply_stickers(&mut stickers);
+        for (i, mv) in "U R U' R'".split(' ').enumerate() {
+            let mv: Move = mv.parse().unwrap();
+            log.record(mv, ms(i as u64 * 400 + 1000));
+            mv.apply_stickers(&mut stickers);
+        }
+        assert_eq!(format_log(log.moves()), "U@1000 R@1400 U'@1800 R'@2200");
+
+        let mut replay = log.replay();
+        replay.seek(usize::MAX);
+        assert_eq!(replay.stickers_at(3, replay.position()), stickers);
+
+        // picking up from the middle of a replay keeps its moves and times
+        replay.seek(2);
+        let mut log = TurnLog::from_replay(&replay, ms(5000));
+        log.record("F".parse().unwrap(), ms(5500));
+        assert_eq!(format_log(log.moves()), "U@1000 R@1400 F@1900");
+        assert_eq!(log.replay().scramble(), &scramble);
+    }
+}
@@ -0,0 +1,226 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::time::Duration;
+
+use crate::timer::format_time;
+
+/// The outcome of a solve or an average of several.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SolveTime {
+    Time(Duration),
+    Dnf,
+}
+
+impl From<Option<Duration>> for SolveTime {
+    fn from(time: Option<Duration>) -> SolveTime {
+        time.map_or(SolveTime::Dnf, SolveTime::Time)
+    }
+}
+
+impl Ord for SolveTime {
+    // a DNF is worse than any time
+    fn cmp(&self, other: &SolveTime) -> Ordering {
+        match (self, other) {
+            (SolveTime::Time(a), SolveTime::Time(b)) => a.cmp(b),
+            (SolveTime::Time(_), SolveTime::Dnf) => Ordering::Less,
+            (SolveTime::Dnf, SolveTime::Time(_)) => Ordering::Greater,
+            (SolveTime::Dnf, SolveTime::Dnf) => Ordering::Equal,
+        }
+    }
+}
+
+impl PartialOrd for SolveTime {
+    fn partial_cmp(&self, other: &SolveTime) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for SolveTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveTime::Time(time) => f.write_str(&format_time(*time)),
+            SolveTime::Dnf => f.write_str("DNF"),
+        }
+    }
+}
+
+/// The mean of all the times, a DNF if any of them is.
+pub fn mean_of(times: &[SolveTime]) -> SolveTime {
+    let mut total = Duration::from_secs(0);
+    for time in times {
+        match time {
+            SolveTime::Time(time) => total += *time,
+            SolveTime::Dnf => return SolveTime::Dnf,
+        }
+    }
+    // rounded to the nearest hundredth, halves up, as the WCA does
+    let centi = 10_000_000 * times.len() as u128;
+    let hundredths = (total.as_nanos() + centi / 2) / centi;
+    SolveTime::Time(Duration::from_millis(hundredths as u64 * 10))
+}
+
+/// The WCA average: the best and worst 5% of the times, rounded up to at
+/// least one each, are dropped and the rest are averaged. DNFs count as the
+/// worst times so one more DNF than is trimmed makes the average a DNF.
+pub fn average_of(times: &[SolveTime]) -> SolveTime {
+    let trim = times.len().div_ceil(20);
+    let mut sorted = times.to_vec();
+    sorted.sort();
+    mean_of(&sorted[trim..times.len() - trim])
+}
+
+/// The latest and best value of a statistic over every window of a session.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rolling {
+    pub current: SolveTime,
+    pub best: SolveTime,
+}
+
+fn rolling(times: &[SolveTime], n: usize, f: fn(&[SolveTime]) -> SolveTime) -> Option<Rolling> {
+    let mut windows = times.windows(n).map(f);
+    let current = windows.next_back()?;
+    let best = windows.fold(current, |best, time| best.min(time));
+    Some(Rolling { current, best })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub count: usize,
+    pub best: Option<SolveTime>,
+    pub worst: Option<SolveTime>,
+    /// The mean of every solve that was not a DNF.
+    pub mean: Option<SolveTime>,
+    pub mo3: Option<Rolling>,
+    pub ao5: Option<Rolling>,
+    pub ao12: Option<Rolling>,
+    pub ao100: Option<Rolling>,
+    /// Turns per second over every solve that was not a DNF and has a move
+    /// count.
+    pub tps: Option<f64>,
+}
+
+impl Stats {
+    /// Works out the statistics for a session given each solve's time, raw
+    /// time without penalties and move count, oldest first. A move count of
+    /// 0 means it is not known.
+    pub fn new(solves: &[(SolveTime, Duration, usize)]) -> Stats {
+        let times: Vec<_> = solves.iter().map(|s| s.0).collect();
+        let finished: Vec<_> = times
+            .iter()
+            .cloned()
+            .filter(|&t| t != SolveTime::Dnf)
+            .collect();
+
+        let (moves, time) = solves
+            .iter()
+            .filter(|s| s.0 != SolveTime::Dnf && s.2 > 0)
+            .fold((0, Duration::from_secs(0)), |(moves, time), s| {
+                (moves + s.2, time + s.1)
+            });
+        let tps = if time > Duration::from_secs(0) {
+            Some(moves as f64 / time.as_secs_f64())
+        } else {
+            None
+        };
+
+        Stats {
+            count: times.len(),
+            best: times.iter().min().cloned(),
+            worst: times.iter().max().cloned(),
+            mean: if finished.is_empty() {
+                None
+            } else {
+                Some(mean_of(&finished))
+            },
+            mo3: rolling(&times, 3, mean_of),
+            ao5: rolling(&times, 5, average_of),
+            ao12: rolling(&times, 12, average_of),
+            ao100: rolling(&times, 100, average_of),
+            tps,
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "solves: {}", self.count)?;
+        if let Some(best) = self.best {
+            writeln!(f, "best: {}", best)?;
+        }
+        if let Some(mean) = self.mean {
+            writeln!(f, "mean: {}", mean)?;
+        }
+
+        let averages = [
+            ("mo3", self.mo3),
+            ("ao5", self.ao5),
+            ("ao12", self.ao12),
+            ("ao100", self.ao100),
+        ];
+        for (name, average) in averages.iter() {
+            if let Some(average) = average {
+                writeln!(f, "{}: {} (best {})", name, average.current, average.best)?;
+            }
+        }
+
+        if let Some(tps) = self.tps {
+            writeln!(f, "tps: {:.2}", tps)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(millis: u64) -> SolveTime {
+        SolveTime::Time(Duration::from_millis(millis))
+    }
+
+    #[test]
+    fn averages() {
+        let times = [t(10000), t(12000), t(9000), t(11000), t(30000)];
+        assert_eq!(average_of(&times), t(11000));
+        assert_eq!(mean_of(&times[..3]), t(10330));
+        assert_eq!(mean_of(&[t(10008), t(10001)]), t(10000));
+        assert_eq!(mean_of(&[t(10008), t(10002)]), t(10010));
+
+        // one DNF is trimmed, two make the average a DNF
+        let times = [t(10000), SolveTime::Dnf, t(9000), t(11000), t(12000)];
+        assert_eq!(average_of(&times), t(11000));
+        let times = [t(10000), SolveTime::Dnf, t(9000), SolveTime::Dnf, t(12000)];
+        assert_eq!(average_of(&times), SolveTime::Dnf);
+        assert_eq!(mean_of(&times[..3]), SolveTime::Dnf);
+
+        // 5 of each end are dropped from an average of 100
+        let mut times: Vec<_> = (1..=100).map(|i| t(i * 1000)).collect();
+        times[0] = SolveTime::Dnf;
+        assert_eq!(average_of(&times), t(51500));
+    }
+
+    #[test]
+    fn stats() {
+        let solves: Vec<_> = [8000, 10000, 12000, 9000, 11000, 7000]
+            .iter()
+            .map(|&ms| (t(ms), Duration::from_millis(ms), 50))
+            .collect();
+        let stats = Stats::new(&solves);
+
+        assert_eq!(stats.count, 6);
+        assert_eq!(stats.best, Some(t(7000)));
+        assert_eq!(stats.worst, Some(t(12000)));
+        assert_eq!(stats.mean, Some(t(9500)));
+        let mo3 = stats.mo3.unwrap();
+        assert_eq!(mo3.current, t(9000));
+        assert_eq!(mo3.best, t(9000));
+        let ao5 = stats.ao5.unwrap();
+        assert_eq!(ao5.current, t(10000));
+        assert_eq!(ao5.best, t(10000));
+        assert_eq!(stats.ao12, None);
+        assert!((stats.tps.unwrap() - 300.0 / 57.0).abs() < 1.0e-9);
+        assert!(stats.to_string().contains("ao5: 10.00 (best 10.00)"));
+
+        assert_eq!(Stats::new(&[]).best, None);
+    }
+}
@@ -1,90 +1,215 @@
-use crate::components::layout;
+use crate::components::algorithm::{Algorithm, DepthError, Move};
+use crate::components::easing::Easing;
+use crate::components::grid::{self, Orientation};
 use crate::components::layout::Layout;
-use crate::components::stickers::{Face, Rotate, Slice, Stickers};
+use crate::components::mesh::MeshStyle;
+use crate::components::picking::{self, Hit, Ray};
+use crate::components::scheme::ColourScheme;
+use crate::components::stickers::{Sticker, Stickers};
 use crate::components::transform::Transform;
+use crate::components::undo::UndoStack;
+use crate::config::{Config, Diagnostic, Modifier, Modifiers};
 use crate::ogl::buffer::ModelBuffer;
 use crate::ogl::render::Renderer;
 use crate::ogl::render::WorldObject;
-use crate::ogl::resources::{Models, ResourceManager, Textures};
+use crate::ogl::resources::upload_mesh;
 use crate::ogl::texture::Texture;
-
-use nalgebra::{Rotation3, UnitQuaternion, Vector3};
-use sdl2::keyboard::Scancode;
+use crate::solver::scramble::Scrambler;
+use crate::solver::{BackgroundSolver, SolveError};
+use crate::timer::history::{Entry, History, DEFAULT_SESSION};
+use crate::timer::replay::{self, Replay, TurnLog};
+use crate::timer::{Solve, Timer};
+
+use nalgebra::{Matrix4, Rotation3, UnitQuaternion, Vector3, U3};
+use sdl2::event::Event;
+use sdl2::keyboard::{Mod, Scancode};
+use sdl2::mouse::MouseButton;
 use sdl2::EventPump;
-use std::rc::Rc;
-
-struct Move {
-    turn: Turn,
-    rev: bool,
-    double: bool,
-    with: bool,
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tobj::Material;
+
+const DEFAULT_QUEUE_LIMIT: usize = 64;
+const DEFAULT_TURN_DURATION: Duration = Duration::from_millis(150);
+// distance between the centres of neighbouring pieces
+const SPACING: f32 = 2.025;
+const BODY_MODEL: usize = 0;
+// followed by one sticker for each face in `Face` order
+const STICKER_MODEL: usize = 1;
+
+const BODY_COLOUR: [f32; 3] = [0.025, 0.025, 0.025];
+const RANDOM_MOVE_SCRAMBLE_LENGTH: usize = 25;
+// keys with a built-in action, which can not be given a move
+const RESERVED_KEYS: [(Scancode, &str); 6] = [
+    (Scancode::Return, "solving"),
+    (Scancode::R, "scrambling"),
+    (Scancode::Tab, "replays"),
+    (Scancode::Z, "undo and redo"),
+    (Scancode::T, "changing the easing"),
+    (Scancode::C, "changing the colour scheme"),
+];
+// how much each press of `[` or `]` changes the replay speed
+const REPLAY_SPEED_STEP: f32 = 2.0;
+
+fn modifier_mask(modifier: Modifier) -> Mod {
+    match modifier {
+        Modifier::Shift => Mod::LSHIFTMOD | Mod::RSHIFTMOD,
+        Modifier::Ctrl => Mod::LCTRLMOD | Mod::RCTRLMOD,
+        Modifier::Alt => Mod::LALTMOD | Mod::RALTMOD,
+    }
 }
 
-#[derive(Copy, Clone)]
-enum Turn {
-    Up,
-    Down,
-    Left,
-    Right,
-    Front,
-    Back,
-    Middle,
-    Equator,
-    Standing,
-    X,
-    Y,
-    Z,
+struct TurnProgress {
+    pieces: Vec<usize>,
+    // the full rotation of the turn as an axis scaled by the angle
+    rot: Vector3<f32>,
+    // seconds
+    duration: f32,
+    elapsed: f32,
+    // the exact rotation of each piece before and after the turn
+    start: Vec<UnitQuaternion<f32>>,
+    end: Vec<UnitQuaternion<f32>>,
 }
 
-impl Turn {
-    fn axis(self) -> Vector3<f32> {
-        match self {
-            Turn::Up | Turn::Y => Vector3::z(),
-            Turn::Down | Turn::Equator => -Vector3::z(),
-            Turn::Left | Turn::Middle => Vector3::x(),
-            Turn::Right | Turn::X => -Vector3::x(),
-            Turn::Front | Turn::Z | Turn::Standing => -Vector3::y(),
-            Turn::Back => Vector3::y(),
+impl TurnProgress {
+    // shows the turn `progress` of the way through
+    fn show(&self, pieces: &mut [Piece], progress: f32) {
+        let rot = UnitQuaternion::from_scaled_axis(self.rot * progress);
+        for (&i, &start) in self.pieces.iter().zip(&self.start) {
+            pieces[i].transform.rot = rot * start;
+        }
+    }
+
+    // puts the pieces exactly where the turn leaves them
+    fn finish(&self, pieces: &mut [Piece]) {
+        for (&i, &end) in self.pieces.iter().zip(&self.end) {
+            pieces[i].transform.rot = end;
         }
     }
 }
 
-struct TurnProgress {
-    pieces: Vec<usize>,
-    rot: Rotation3<f32>,
-    steps: usize,
+// the rotation of a piece in model space from its discrete orientation
+fn model_rotation(orientation: Orientation) -> UnitQuaternion<f32> {
+    let grid_to_model = UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(
+        grid_to_model().fixed_slice::<U3, U3>(0, 0).into_owned(),
+    ));
+    grid_to_model * orientation.to_quaternion() * grid_to_model.inverse()
 }
 
-#[derive(Clone, Copy)]
 struct Piece {
     transform: Transform,
-    model: &'static [usize],
+    // the meshes drawn for this piece, indices into `Cube::buffer`
+    model: Vec<usize>,
+    // moves the mesh from the origin to its place on the cube
+    offset: Matrix4<f32>,
 }
 
 impl Piece {
-    fn new(model: &'static [usize]) -> Piece {
-        let transform = Transform::default();
-        Piece { transform, model }
+    fn for_position(size: usize, index: usize) -> Piece {
+        let p = grid::piece_point(size, index);
+        let outer = size as i32 - 1;
+
+        // the body and then a sticker for each outward face
+        let mut model = Vec::new();
+        for &face in &grid::FACES {
+            let normal = grid::normal(face);
+            if (0..3).any(|i| normal[i] != 0 && p[i] == normal[i] * outer) {
+                model.push(STICKER_MODEL + face as usize);
+            }
+        }
+        if !model.is_empty() {
+            model.insert(0, BODY_MODEL);
+        }
+
+        let offset = Vector3::new(p[0] as f32, p[1] as f32, p[2] as f32) * SPACING / 2.0;
+        Piece {
+            transform: Transform::default(),
+            model,
+            offset: grid_to_model() * Matrix4::new_translation(&offset),
+        }
+    }
+}
+
+// meshes are built with y up and z to the front while pieces turn around the
+// axes the cube model was made with, which has y to the front and z down
+fn grid_to_model() -> Matrix4<f32> {
+    Matrix4::new(
+        1.0, 0.0, 0.0, 0.0, //
+        0.0, 0.0, 1.0, 0.0, //
+        0.0, -1.0, 0.0, 0.0, //
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+fn material(diffuse: [f32; 3]) -> Material {
+    let mut material = Material::empty();
+    material.ambient = [1.0; 3];
+    material.diffuse = diffuse;
+    material.specular = [0.5; 3];
+    material.shininess = 96.0;
+    material
+}
+
+fn build_models(style: &MeshStyle, scheme: &ColourScheme) -> Vec<ModelBuffer> {
+    let mut models = vec![upload_mesh(
+        &style.body(SPACING),
+        Some(material(BODY_COLOUR)),
+    )];
+    for &face in &grid::FACES {
+        let mesh = style.sticker(SPACING, face);
+        models.push(upload_mesh(
+            &mesh,
+            Some(material(scheme.colour(Sticker::from_home(face)))),
+        ));
     }
+    models
 }
 
 pub struct Cube {
-    buffer: Rc<Vec<ModelBuffer>>,
-    texture: Rc<Texture>,
-    pieces: [Piece; 27],
-    turn: Option<TurnProgress>,
+    buffer: Vec<ModelBuffer>,
+    texture: Texture,
+    style: MeshStyle,
+    // the schemes that can be switched between and the one in use
+    schemes: Vec<ColourScheme>,
+    scheme: usize,
+    size: usize,
+    pieces: Vec<Piece>,
+    turns: VecDeque<TurnProgress>,
+    queue_limit: usize,
+    turn_duration: Duration,
+    easing: Easing,
+    // the move each key makes and the modifiers that change it
+    keys: Vec<(Scancode, Move)>,
+    modifiers: Modifiers,
     transform: Transform,
     layout: Layout,
     stickers: Stickers,
+    solver: Option<BackgroundSolver>,
+    scrambler: Scrambler,
+    // where the cube was grabbed while the mouse button is held
+    drag: Option<Hit>,
+    timer: Timer,
+    // a scramble whose inspection starts once it has finished animating
+    pending_inspection: Option<Algorithm>,
+    solves: Vec<Solve>,
+    history: Option<History>,
+    // the user's turns since the last scramble
+    undo: UndoStack,
+    // the turns made since the last scramble, for replaying them
+    log: TurnLog,
+    // while set the cube follows a recorded solve and ignores the user
+    replay: Option<Replay>,
 }
 
 impl WorldObject for Cube {
     fn on_render(&mut self, renderer: &Renderer) {
         self.texture.bind();
 
-        for peiece in &self.pieces {
-            renderer.set_model(self.transform.model() * peiece.transform.model());
-            for &model in peiece.model {
+        let scale = Matrix4::new_scaling(3.0 / self.size as f32);
+        for piece in &self.pieces {
+            renderer
+                .set_model(self.transform.model() * scale * piece.transform.model() * piece.offset);
+            for &model in &piece.model {
                 self.buffer[model].draw(renderer);
             }
         }
@@ -92,54 +217,57 @@ impl WorldObject for Cube {
         Texture::unbind();
     }
 
-    fn on_tick(&mut self, event_pump: &EventPump, _renderer: &Renderer) {
-        self.handle_input(event_pump);
-        self.tick_turn();
+    fn on_tick(&mut self, _event_pump: &EventPump, renderer: &Renderer) {
+        self.tick_replay(renderer.frame_time());
+        self.tick_turn(renderer.frame_time());
+        self.tick_timer(Instant::now());
+    }
+
+    fn status(&self) -> Option<String> {
+        if let Some(replay) = &self.replay {
+            return Some(replay.to_string());
+        }
+        Some(self.timer.display(Instant::now())).filter(|display| !display.is_empty())
+    }
+
+    fn on_event(&mut self, event: &Event, renderer: &Renderer) {
+        match *event {
+            Event::KeyDown {
+                scancode: Some(scancode),
+                keymod,
+                repeat: false,
+                ..
+            } => self.handle_key(scancode, keymod),
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } if self.replay.is_none() => {
+                let ray = self.grid_ray(renderer.screen_ray(x, y));
+                self.drag = picking::pick(self.size, &ray);
+            }
+            Event::MouseMotion { x, y, .. } => self.handle_drag(renderer.screen_ray(x, y)),
+            Event::MouseButtonUp {
+                mouse_btn: MouseButton::Left,
+                ..
+            } => self.drag = None,
+            _ => {}
+        }
     }
 }
 
 impl Cube {
-    pub fn new(manager: &mut ResourceManager) -> Cube {
-        let spot_mod = manager.load_model(Models::Cube).unwrap();
-        //let spot_mod = manager.load_model(Models::Spot).unwrap();
-        let spot_tex = manager.load_texture(Textures::Spot).unwrap();
-
-        use crate::components::piece::*;
-        let _pieces = [Piece::new(&[]); 27];
-        let pieces = [
-            // blue layer
-            Piece::new(&BOY),
-            Piece::new(&BY),
-            Piece::new(&BRY),
-            Piece::new(&BO),
-            Piece::new(&B),
-            Piece::new(&BR),
-            Piece::new(&BOW),
-            Piece::new(&BW),
-            Piece::new(&BRW),
-            // middle layer
-            Piece::new(&OY),
-            Piece::new(&Y),
-            Piece::new(&RY),
-            Piece::new(&O),
-            Piece::new(&C),
-            Piece::new(&R),
-            Piece::new(&OW),
-            Piece::new(&W),
-            Piece::new(&RW),
-            // green layer
-            Piece::new(&GOY),
-            Piece::new(&GY),
-            Piece::new(&GRY),
-            Piece::new(&GO),
-            Piece::new(&G),
-            Piece::new(&GR),
-            Piece::new(&GOW),
-            Piece::new(&GW),
-            Piece::new(&GRW),
-        ];
-
-        let layout = Layout::new();
+    /// Creates a solved cube with `size` pieces along each edge.
+    pub fn new(size: usize) -> Cube {
+        let style = MeshStyle::default();
+        let schemes = ColourScheme::builtin();
+
+        let pieces = (0..size * size * size)
+            .map(|i| Piece::for_position(size, i))
+            .collect();
+
+        let layout = Layout::with_size(size);
         let mut transform = Transform::default();
         transform.rot_offset = UnitQuaternion::from(Rotation3::from_euler_angles(
             f32::to_radians(-90.0),
@@ -147,292 +275,649 @@ impl Cube {
             0.0,
         ));
 
-        Cube {
-            buffer: spot_mod,
-            texture: spot_tex,
+        let mut cube = Cube {
+            buffer: build_models(&style, &schemes[0]),
+            texture: Texture::solid([255; 3]),
+            style,
+            schemes,
+            scheme: 0,
+            size,
             pieces,
-            turn: None,
+            turns: VecDeque::new(),
+            queue_limit: DEFAULT_QUEUE_LIMIT,
+            turn_duration: DEFAULT_TURN_DURATION,
+            easing: Easing::default(),
+            keys: Vec::new(),
+            modifiers: Modifiers::default(),
             transform,
             layout,
-            stickers: Stickers::new(),
-        }
+            stickers: Stickers::with_size(size),
+            solver: None,
+            scrambler: Scrambler::new(),
+            drag: None,
+            timer: Timer::new(),
+            pending_inspection: None,
+            solves: Vec::new(),
+            history: None,
+            undo: UndoStack::new(),
+            log: TurnLog::new(Algorithm::new(), Instant::now()),
+            replay: None,
+        };
+
+        let config = Config::default();
+        cube.set_keys(&config.keys, config.modifiers);
+        cube
+    }
+
+    /// Applies the animation, colour and key settings from the config,
+    /// returning a diagnostic for each key SDL does not know, that has a
+    /// built-in action or whose move is too deep for the cube.
+    pub fn configure(&mut self, config: &Config) -> Vec<Diagnostic> {
+        self.turn_duration = config.turn_duration;
+        self.easing = config.easing;
+        for scheme in &config.schemes {
+            self.set_scheme(scheme.clone());
+        }
+        self.set_scheme(config.scheme.clone());
+        self.set_keys(&config.keys, config.modifiers)
     }
 
-    fn handle_input(&mut self, event_pump: &EventPump) {
-        let kb = &event_pump.keyboard_state();
-        let rev = kb.is_scancode_pressed(Scancode::LShift);
-        let double = kb.is_scancode_pressed(Scancode::LCtrl);
-        let with = kb.is_scancode_pressed(Scancode::LAlt);
+    /// Binds each key, as named by SDL, to the move it makes. Keys with a
+    /// built-in action keep it.
+    pub fn set_keys(&mut self, keys: &[(String, Move)], modifiers: Modifiers) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        self.keys.clear();
+        for (name, mv) in keys {
+            let message = match Scancode::from_name(name) {
+                Some(scancode) => match RESERVED_KEYS.iter().find(|&&(key, _)| key == scancode) {
+                    Some((_, action)) => format!("key is reserved for {}", action),
+                    None if mv.depth > self.size => DepthError {
+                        mv: *mv,
+                        size: self.size,
+                    }
+                    .to_string(),
+                    None => {
+                        self.keys.push((scancode, *mv));
+                        continue;
+                    }
+                },
+                None => "unknown key".to_string(),
+            };
+            diagnostics.push(Diagnostic {
+                key: format!("keys.{:?}", name),
+                message,
+            });
+        }
+        self.modifiers = modifiers;
+        diagnostics
+    }
 
-        if kb.is_scancode_pressed(Scancode::W) {
-            self.transform.translate(Vector3::z() * 0.1);
-        }
-        if kb.is_scancode_pressed(Scancode::A) {
-            self.transform.translate(-Vector3::x() * 0.1);
-        }
-        if kb.is_scancode_pressed(Scancode::D) {
-            self.transform.translate(Vector3::x() * 0.1);
-        }
-        if kb.is_scancode_pressed(Scancode::S) {
-            self.transform.translate(-Vector3::z() * 0.1);
-        }
-        if kb.is_scancode_pressed(Scancode::Left) {
-            self.transform.rotate_euler(Rotation3::from_euler_angles(
-                0.0,
-                f32::to_radians(-4.0),
-                0.0,
-            ))
+    pub fn style(&self) -> &MeshStyle {
+        &self.style
+    }
+
+    /// Rebuilds the piece meshes with a new bevel, gap and sticker inset.
+    pub fn set_style(&mut self, style: MeshStyle) {
+        self.buffer = build_models(&style, self.scheme());
+        self.style = style;
+    }
+
+    pub fn scheme(&self) -> &ColourScheme {
+        &self.schemes[self.scheme]
+    }
+
+    /// Recolours the stickers, replacing any scheme with the same name in
+    /// the ones `C` cycles through.
+    pub fn set_scheme(&mut self, scheme: ColourScheme) {
+        self.scheme = match self.schemes.iter().position(|s| s.name == scheme.name) {
+            Some(i) => {
+                self.schemes[i] = scheme;
+                i
+            }
+            None => {
+                self.schemes.push(scheme);
+                self.schemes.len() - 1
+            }
+        };
+        self.update_colours();
+    }
+
+    fn next_scheme(&mut self) {
+        self.scheme = (self.scheme + 1) % self.schemes.len();
+        self.update_colours();
+        println!("colour scheme: {}", self.scheme().name);
+    }
+
+    fn update_colours(&mut self) {
+        let scheme = &self.schemes[self.scheme];
+        for &face in &grid::FACES {
+            let colour = scheme.colour(Sticker::from_home(face));
+            self.buffer[STICKER_MODEL + face as usize].set_diffuse(colour);
         }
-        if kb.is_scancode_pressed(Scancode::Right) {
-            self.transform.rotate_euler(Rotation3::from_euler_angles(
-                0.0,
-                f32::to_radians(4.0),
-                0.0,
-            ))
+    }
+
+    fn handle_key(&mut self, scancode: Scancode, keymod: Mod) {
+        if self.replay.is_some() {
+            return self.handle_replay_key(scancode);
         }
-        if kb.is_scancode_pressed(Scancode::Q) {
-            self.transform.rotate_euler(Rotation3::from_euler_angles(
-                0.0,
-                0.0,
-                f32::to_radians(-4.0),
-            ))
+
+        let held = |modifier| keymod.intersects(modifier_mask(modifier));
+
+        if let Some(&(_, mv)) = self.keys.iter().find(|&&(key, _)| key == scancode) {
+            let m = self.modifiers;
+            return self.turn(mv.modified(held(m.reverse), held(m.double), held(m.with)));
         }
-        if kb.is_scancode_pressed(Scancode::E) {
-            self.transform.rotate_euler(Rotation3::from_euler_angles(
-                0.0,
-                0.0,
-                f32::to_radians(4.0),
-            ))
+
+        let (ctrl, shift) = (held(Modifier::Ctrl), held(Modifier::Shift));
+        match scancode {
+            Scancode::Return => self.solve(),
+            Scancode::R => self.scramble(shift),
+            Scancode::Tab => self.start_replay(),
+            Scancode::Z if ctrl && shift => self.redo(),
+            Scancode::Z if ctrl => self.undo(),
+            Scancode::T => {
+                self.easing = self.easing.next();
+                println!("easing: {}", self.easing);
+            }
+            Scancode::C => self.next_scheme(),
+            _ => {}
         }
-        if kb.is_scancode_pressed(Scancode::Up) {
-            self.transform.rotate_euler(Rotation3::from_euler_angles(
-                f32::to_radians(-4.0),
-                0.0,
-                0.0,
-            ))
+    }
+
+    fn handle_replay_key(&mut self, scancode: Scancode) {
+        let replay = match &mut self.replay {
+            Some(replay) => replay,
+            None => return,
+        };
+
+        match scancode {
+            Scancode::Escape | Scancode::Tab => return self.stop_replay(),
+            Scancode::Space => replay.set_paused(!replay.is_paused()),
+            Scancode::LeftBracket => replay.set_speed(replay.speed() / REPLAY_SPEED_STEP),
+            Scancode::RightBracket => replay.set_speed(replay.speed() * REPLAY_SPEED_STEP),
+            Scancode::Period => {
+                if let Some(step) = replay.step_forward() {
+                    self.queue_turn(step.apply());
+                }
+            }
+            Scancode::Comma => {
+                if let Some(step) = replay.step_back() {
+                    self.queue_turn(step.apply());
+                }
+            }
+            Scancode::PageDown => {
+                let position = replay.position() + 1;
+                self.seek_replay(position);
+            }
+            Scancode::PageUp => {
+                let position = replay.position().saturating_sub(1);
+                self.seek_replay(position);
+            }
+            Scancode::Home => self.seek_replay(0),
+            Scancode::End => self.seek_replay(usize::MAX),
+            _ => return,
         }
-        if kb.is_scancode_pressed(Scancode::Down) {
-            self.transform.rotate_euler(Rotation3::from_euler_angles(
-                f32::to_radians(4.0),
-                0.0,
-                0.0,
-            ))
+
+        if let Some(replay) = &self.replay {
+            println!("{}", replay);
         }
-        if kb.is_scancode_pressed(Scancode::Equals) {
-            self.transform.scale += Vector3::repeat(0.02);
+    }
+
+    // converts a world space ray to grid space, where pieces at rest are two
+    // units apart and centred on the origin
+    fn grid_ray(&self, ray: Ray) -> Ray {
+        let grid = self.transform.model()
+            * Matrix4::new_scaling(3.0 / self.size as f32)
+            * grid_to_model()
+            * Matrix4::new_scaling(SPACING / 2.0);
+        ray.transform(&grid.try_inverse().unwrap())
+    }
+
+    // turns the grabbed layer once the drag shows which way it should go
+    fn handle_drag(&mut self, ray: Ray) {
+        let hit = match self.drag {
+            Some(hit) => hit,
+            None => return,
+        };
+
+        let ray = self.grid_ray(ray);
+        if let Some(mv) = picking::drag_move(self.size, &hit, &ray) {
+            self.drag = None;
+            self.turn(mv);
         }
-        if kb.is_scancode_pressed(Scancode::Minus) {
-            self.transform.scale -= Vector3::repeat(0.02);
+    }
+
+    fn tick_timer(&mut self, now: Instant) {
+        if self.turns.is_empty() {
+            if let Some(scramble) = self.pending_inspection.take() {
+                println!("inspection started");
+                self.timer.start_inspection(scramble, now);
+            }
         }
-        if kb.is_scancode_pressed(Scancode::Space) {
-            //self.transform.look_at(Vector3::zeros());
+
+        if let Some(secs) = self.timer.take_warning(now) {
+            println!("{} seconds", secs);
         }
-        if kb.is_scancode_pressed(Scancode::U) {
-            self.transform
-                .look_at(self.transform.pos.coords - Vector3::y());
+        if let Some(solve) = self.timer.update(now) {
+            self.record_solve(solve);
         }
-        if kb.is_scancode_pressed(Scancode::Num1) {
-            self.turn(Turn::Left, 80, rev, double, with);
+    }
+
+    // saves the solve to the history, which is loaded the first time a solve
+    // is finished, and shows the updated statistics
+    fn record_solve(&mut self, solve: Solve) {
+        println!("solve: {}", solve);
+
+        if self.history.is_none() {
+            match History::open_default() {
+                Ok(history) => self.history = Some(history),
+                Err(e) => eprintln!("failed to load solve history: {}", e),
+            }
         }
-        if kb.is_scancode_pressed(Scancode::Num2) {
-            self.turn(Turn::Right, 80, rev, double, with);
+        if let Some(history) = &mut self.history {
+            match history.add(Entry::from_solve(&solve)) {
+                Ok(()) => print!("{}", history.session_stats(DEFAULT_SESSION)),
+                Err(e) => eprintln!("failed to save solve: {}", e),
+            }
         }
-        if kb.is_scancode_pressed(Scancode::Num3) {
-            self.turn(Turn::Up, 80, rev, double, with);
+
+        self.solves.push(solve);
+    }
+
+    /// Replays the turns made since the last scramble. Without any, the
+    /// last solve from this run or from the history is replayed instead.
+    pub fn start_replay(&mut self) {
+        let replay = if !self.log.moves().is_empty() {
+            self.log.replay()
+        } else if let Some(solve) = self.solves.last() {
+            Replay::from_solve(solve)
+        } else {
+            match self.last_recorded_solve() {
+                Some(replay) => replay,
+                None => return eprintln!("no recorded solve to replay"),
+            }
+        };
+        self.play(replay);
+    }
+
+    // the last solve in the history with its moves recorded
+    fn last_recorded_solve(&mut self) -> Option<Replay> {
+        if self.history.is_none() {
+            self.history = History::open_default().ok();
+        }
+        let entry = self
+            .history
+            .as_ref()?
+            .entries()
+            .iter()
+            .rev()
+            .find(|e| !e.reconstruction.is_empty())?;
+
+        let scramble = match entry.scramble.parse() {
+            Ok(scramble) => scramble,
+            Err(e) => {
+                eprintln!("invalid scramble in history: {}", e);
+                return None;
+            }
+        };
+        match replay::parse_log(&entry.reconstruction) {
+            Ok(moves) => Some(Replay::new(scramble, moves)),
+            Err(e) => {
+                eprintln!("{}", e);
+                None
+            }
         }
-        if kb.is_scancode_pressed(Scancode::Num4) {
-            self.turn(Turn::Down, 80, rev, double, with);
+    }
+
+    /// Follows a recorded solve from its scrambled state. User turns are
+    /// ignored until the replay is stopped.
+    pub fn play(&mut self, replay: Replay) {
+        self.timer.cancel();
+        self.pending_inspection = None;
+        self.drag = None;
+
+        println!("replaying: {}", replay.scramble());
+        self.replay = Some(replay);
+        self.seek_replay(0);
+    }
+
+    /// Leaves the cube where the replay was and gives control back. The turn
+    /// log carries on from there.
+    pub fn stop_replay(&mut self) {
+        if let Some(replay) = self.replay.take() {
+            self.log = TurnLog::from_replay(&replay, Instant::now());
+            println!("replay stopped");
         }
-        if kb.is_scancode_pressed(Scancode::Num5) {
-            self.turn(Turn::Front, 80, rev, double, with);
+    }
+
+    /// Jumps to just after the first `position` moves of the replay.
+    pub fn seek_replay(&mut self, position: usize) {
+        let moves = match &mut self.replay {
+            Some(replay) => {
+                replay.seek(position);
+                replay.moves_until(replay.position())
+            }
+            None => return,
+        };
+
+        // replaying from solved rather than turning back keeps every seek
+        // to the same move identical
+        self.reset();
+        for mv in moves {
+            self.queue_turn(mv);
         }
-        if kb.is_scancode_pressed(Scancode::Num6) {
-            self.turn(Turn::Back, 80, rev, double, with);
+        self.skip_animation();
+    }
+
+    fn tick_replay(&mut self, dt: f32) {
+        let steps = match &mut self.replay {
+            Some(replay) => replay.advance(Duration::from_secs_f32(dt)),
+            None => return,
+        };
+
+        for step in steps {
+            self.queue_turn(step.apply());
         }
-        if kb.is_scancode_pressed(Scancode::Num7) {
-            self.turn(Turn::X, 80, rev, double, with);
+    }
+
+    /// Solves finished with the timer, oldest first.
+    pub fn solves(&self) -> &[Solve] {
+        &self.solves
+    }
+
+    // advances the animation by `dt` seconds, moving on to the next turns
+    // with whatever time is left when one finishes
+    fn tick_turn(&mut self, mut dt: f32) {
+        while let Some(turn) = self.turns.front_mut() {
+            turn.elapsed += dt;
+            if turn.elapsed < turn.duration {
+                let progress = self.easing.apply(turn.elapsed / turn.duration);
+                return turn.show(&mut self.pieces, progress);
+            }
+
+            dt = turn.elapsed - turn.duration;
+            self.finish_turn();
         }
-        if kb.is_scancode_pressed(Scancode::Num8) {
-            self.turn(Turn::Y, 80, rev, double, with);
+    }
+
+    /// Sets how many turns may be waiting to animate. Once the limit is
+    /// exceeded the oldest turns are completed instantly so no move is lost.
+    pub fn set_queue_limit(&mut self, limit: usize) {
+        self.queue_limit = limit;
+        self.enforce_queue_limit();
+    }
+
+    /// Sets how long a quarter turn takes to animate. Half turns take twice
+    /// as long.
+    pub fn set_turn_duration(&mut self, duration: Duration) {
+        self.turn_duration = duration;
+    }
+
+    pub fn set_easing(&mut self, easing: Easing) {
+        self.easing = easing;
+    }
+
+    /// Completes every queued turn instantly.
+    pub fn skip_animation(&mut self) {
+        while !self.turns.is_empty() {
+            self.finish_turn();
         }
-        if kb.is_scancode_pressed(Scancode::Num9) {
-            self.turn(Turn::Z, 80, rev, double, with);
+    }
+
+    /// Animates the algorithm, turning nothing if a move reaches past the
+    /// last layer of the cube.
+    pub fn apply(&mut self, alg: &Algorithm) -> Result<(), DepthError> {
+        alg.check_depth(self.size)?;
+        self.queue_moves(alg);
+        self.log_moves(alg);
+        Ok(())
+    }
+
+    // for algorithms made for this cube, such as solutions and scrambles
+    fn queue_moves(&mut self, alg: &Algorithm) {
+        for mv in alg.moves() {
+            self.queue_turn(mv);
         }
-        if kb.is_scancode_pressed(Scancode::I) {
-            self.turn(Turn::Middle, 80, rev, double, with);
+    }
+
+    fn log_moves(&mut self, alg: &Algorithm) {
+        let now = Instant::now();
+        for mv in alg.moves() {
+            self.log.record(mv, now);
         }
-        if kb.is_scancode_pressed(Scancode::O) {
-            self.turn(Turn::Equator, 80, rev, double, with);
+    }
+
+    /// Starts generating the solver tables on a background thread, so the
+    /// first solve or random-state scramble does not have to wait for them.
+    /// Tables that failed to generate are tried again.
+    pub fn prepare_solver(&mut self) {
+        if self.size != 3 {
+            return;
         }
-        if kb.is_scancode_pressed(Scancode::P) {
-            self.turn(Turn::Standing, 80, rev, double, with);
+        match &mut self.solver {
+            Some(solver) => solver.retry(),
+            None => self.solver = Some(BackgroundSolver::spawn()),
         }
+    }
 
-        if kb.is_scancode_pressed(Scancode::V) {
-            for piece in &mut self.pieces[0..=9] {
-                piece.transform.translate(Vector3::z() * 0.1);
-            }
-        }
+    /// Animates a solution for the current state. Nothing happens while the
+    /// solver tables are still being generated.
+    pub fn solve(&mut self) {
+        if self.size != 3 {
+            return eprintln!(
+                "failed to solve: {}",
+                SolveError::UnsupportedSize(self.size)
+            );
+        }
+        self.prepare_solver();
+        let solver = match self.solver.as_mut().unwrap().get() {
+            Ok(solver) => solver,
+            Err(e) => return eprintln!("failed to solve: {}", e),
+        };
 
-        if kb.is_scancode_pressed(Scancode::Z) {
-            self.pieces[0].transform.translate(Vector3::y() * 0.1);
+        self.timer.cancel();
+        self.pending_inspection = None;
+        self.undo.clear();
+        match solver.solve(&self.stickers) {
+            Ok(solution) => {
+                println!("solution: {}", solution);
+                self.queue_moves(&solution);
+                self.log_moves(&solution);
+            }
+            Err(e) => eprintln!("failed to solve: {}", e),
         }
     }
 
-    fn tick_turn(&mut self) {
-        let turn = match &mut self.turn {
-            Some(turn) => turn,
-            None => return,
+    /// Resets the cube and animates a scramble to a random state, or a
+    /// cheaper sequence of random moves if `random_moves` is set. Cubes other
+    /// than the 3x3 are always scrambled with random moves.
+    pub fn scramble(&mut self, random_moves: bool) {
+        let scramble = if random_moves {
+            self.scrambler
+                .random_moves(self.size, RANDOM_MOVE_SCRAMBLE_LENGTH)
+        } else if self.size != 3 {
+            let length = RANDOM_MOVE_SCRAMBLE_LENGTH * (self.size - 2);
+            self.scrambler.random_moves(self.size, length)
+        } else {
+            self.prepare_solver();
+            let solver = match self.solver.as_mut().unwrap().get() {
+                Ok(solver) => solver,
+                Err(e) => return eprintln!("failed to scramble: {}", e),
+            };
+            match self.scrambler.random_state_scramble(solver) {
+                Ok(scramble) => scramble,
+                Err(e) => return eprintln!("failed to scramble: {}", e),
+            }
         };
 
-        for &piece in &turn.pieces {
-            let piece = &mut self.pieces[piece];
-            piece.transform.rotate_euler(turn.rot);
+        println!("scramble: {}", scramble);
+        self.timer.cancel();
+        self.reset();
+        self.queue_moves(&scramble);
+        self.log = TurnLog::new(scramble.clone(), Instant::now());
+        self.pending_inspection = Some(scramble);
+    }
+
+    /// Puts the cube back to solved straight away, dropping queued turns and
+    /// starting a new turn log.
+    pub fn reset(&mut self) {
+        self.turns.clear();
+        self.undo.clear();
+        self.log = TurnLog::new(Algorithm::new(), Instant::now());
+        self.stickers = Stickers::with_size(self.size);
+        self.layout = Layout::with_size(self.size);
+        for piece in &mut self.pieces {
+            piece.transform.rot = UnitQuaternion::identity();
         }
+    }
 
-        turn.steps -= 1;
+    fn finish_turn(&mut self) {
+        if let Some(turn) = self.turns.pop_front() {
+            turn.finish(&mut self.pieces);
+        }
+    }
 
-        if turn.steps == 0 {
-            self.turn = None;
+    fn enforce_queue_limit(&mut self) {
+        while self.turns.len() > self.queue_limit {
+            self.finish_turn();
         }
     }
 
-    fn turn_inner(&mut self, turn: Turn, rev: bool, with: bool) -> (Vector3<f32>, Vec<layout::Face>) {
-        let dir = turn.axis();
-        let mut faces;
+    fn queue_turn(&mut self, mv: Move) {
+        mv.apply_stickers(&mut self.stickers);
+        let before: Vec<_> = (0..self.pieces.len())
+            .map(|i| self.layout.orientation(i))
+            .collect();
+        let pieces = mv.apply_layout(&mut self.layout);
 
-        match turn {
-            Turn::Up => {
-                self.stickers.face(Face::Up, rev);
-                faces = vec![layout::UP.reverse(rev)];
-            }
-            Turn::Down => {
-                self.stickers.face(Face::Down, rev);
-                faces = vec![layout::DOWN.reverse(rev)];
-            }
-            Turn::Left => {
-                self.stickers.face(Face::Left, rev);
-                faces = vec![layout::LEFT.reverse(rev)];
-            }
-            Turn::Right => {
-                self.stickers.face(Face::Right, rev);
-                faces = vec![layout::RIGHT.reverse(rev)];
-            }
-            Turn::Front => {
-                self.stickers.face(Face::Front, rev);
-                faces = vec![layout::FRONT.reverse(rev)];
-            }
-            Turn::Back => {
-                self.stickers.face(Face::Back, rev);
-                faces = vec![layout::BACK.reverse(rev)];
-            }
-            Turn::X => {
-                self.stickers.rotate(Rotate::X, rev);
-                faces = vec![
-                    layout::MIDDLE.reverse(!rev),
-                    layout::LEFT.reverse(!rev),
-                    layout::RIGHT.reverse(rev),
-                ];
-            }
-            Turn::Y => {
-                self.stickers.rotate(Rotate::Y, rev);
-                faces = vec![
-                    layout::EQUATOR.reverse(!rev),
-                    layout::UP.reverse(rev),
-                    layout::DOWN.reverse(!rev),
-                ];
-            }
-            Turn::Z => {
-                self.stickers.rotate(Rotate::Z, rev);
-                faces = vec![
-                    layout::STANDING.reverse(rev),
-                    layout::BACK.reverse(!rev),
-                    layout::FRONT.reverse(rev),
-                ];
-            }
-            Turn::Middle => {
-                self.stickers.slice(Slice::Middle, rev);
-                faces = vec![layout::MIDDLE.reverse(rev)];
-            }
-            Turn::Equator => {
-                self.stickers.slice(Slice::Equator, rev);
-                faces = vec![layout::EQUATOR.reverse(rev)];
-            }
-            Turn::Standing => {
-                self.stickers.slice(Slice::Standing, rev);
-                faces = vec![layout::STANDING.reverse(rev)];
-            }
-        }
+        // animating from and to the discrete orientations means rounding
+        // errors cannot build up however many turns are made
+        let start = pieces.iter().map(|&i| model_rotation(before[i])).collect();
+        let end = pieces
+            .iter()
+            .map(|&i| model_rotation(self.layout.orientation(i)))
+            .collect();
 
-        if with {
-            match turn {
-                Turn::Up => {
-                    self.stickers.slice(Slice::Equator, !rev);
-                    faces.push(layout::EQUATOR.reverse(!rev));
-                }
-                Turn::Down => {
-                    self.stickers.slice(Slice::Equator, rev);
-                    faces.push(layout::EQUATOR.reverse(rev));
-                }
-                Turn::Left => {
-                    self.stickers.slice(Slice::Middle, rev);
-                    faces.push(layout::MIDDLE.reverse(rev));
-                }
-                Turn::Right => {
-                    self.stickers.slice(Slice::Middle, !rev);
-                    faces.push(layout::MIDDLE.reverse(!rev));
-                }
-                Turn::Front => {
-                    self.stickers.slice(Slice::Standing, rev);
-                    faces.push(layout::STANDING.reverse(rev));
-                }
-                Turn::Back => {
-                    self.stickers.slice(Slice::Standing, !rev);
-                    faces.push(layout::STANDING.reverse(!rev));
-                }
-                _ => (),
-            }
+        let (angle, quarters) = if mv.double { (180.0, 2) } else { (90.0, 1) };
+        let mut rot = mv.turn.axis() * f32::to_radians(angle);
+        if mv.rev {
+            rot *= -1.0;
         }
 
-        (dir, faces)
+        self.turns.push_back(TurnProgress {
+            pieces,
+            rot,
+            duration: (self.turn_duration * quarters).as_secs_f32(),
+            elapsed: 0.0,
+            start,
+            end,
+        });
+        self.enforce_queue_limit();
     }
 
-    fn turn(&mut self, turn: Turn, speed: usize, rev: bool, double: bool, with: bool) {
-        assert!(speed <= 100);
-        if self.turn.is_some() {
-            return;
+    /// Makes a move for the user, timing it if a solve is in progress. Does
+    /// nothing during a replay.
+    pub fn turn(&mut self, mv: Move) {
+        if self.replay.is_none() {
+            self.undo.push(mv);
+            self.user_turn(mv);
         }
+    }
 
-        let mut pieces = Vec::new();
-        let (mut dir, faces) = self.turn_inner(turn, rev, with);
-
-        if double {
-            self.turn_inner(turn, rev, with);
+    /// Animates the reverse of the last move the user made since the cube
+    /// was scrambled.
+    pub fn undo(&mut self) {
+        if let Some(mv) = self.undo.undo() {
+            self.user_turn(mv);
         }
+    }
 
-        if rev {
-            dir *= -1.0;
+    /// Makes the last undone move again.
+    pub fn redo(&mut self) {
+        if let Some(mv) = self.undo.redo() {
+            self.user_turn(mv);
         }
+    }
 
-        for face in faces.iter() {
-            self.layout.turn(face);
-            if double {
-                self.layout.turn(face);
-            }
-            pieces.extend(&self.layout.layer(face));
+    // undoing during a solve is timed like any other turn
+    fn user_turn(&mut self, mv: Move) {
+        let now = Instant::now();
+        self.queue_turn(mv);
+        self.log.record(mv, now);
+        if let Some(solve) = self.timer.on_move(mv, self.stickers.solved(), now) {
+            self.record_solve(solve);
         }
+    }
+}
 
-        let mut steps = 101 - speed;
-        let dir = dir * f32::to_radians(90.0) / steps as f32;
-        let rot = Rotation3::from_euler_angles(dir.x, dir.y, dir.z);
-        if double {
-            steps *= 2
-        };
-        let turn = TurnProgress { pieces, rot, steps };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::stickers::{MAX_SIZE, MIN_SIZE};
+    use crate::ogl::render::test_renderer;
 
-        self.turn = Some(turn);
+    #[test]
+    fn scramble_from_any_state() {
+        let (_lock, _renderer) = test_renderer();
 
-        println!("{:?}", self.stickers);
-        println!("solved {}", self.stickers.solved());
+        let mut solved = Cube::new(3);
+        solved.scrambler = Scrambler::from_seed(7);
+        solved.scramble(true);
+
+        let mut turned = Cube::new(3);
+        turned.scrambler = Scrambler::from_seed(7);
+        turned.apply(&"R U F' M".parse().unwrap()).unwrap();
+        turned.scramble(true);
+
+        assert_eq!(turned.stickers, solved.stickers);
+    }
+
+    #[test]
+    fn reserved_keys() {
+        let (_lock, _renderer) = test_renderer();
+
+        let mut cube = Cube::new(3);
+        let keys = [("R", "R"), ("J", "U"), ("Return", "y")]
+            .iter()
+            .map(|&(key, mv)| (key.to_string(), mv.parse().unwrap()))
+            .collect::<Vec<_>>();
+        let diagnostics = cube.set_keys(&keys, Modifiers::default());
+
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "keys.\"R\": key is reserved for scrambling",
+                "keys.\"Return\": key is reserved for solving",
+            ]
+        );
+        assert_eq!(cube.keys, [(Scancode::J, keys[1].1)]);
+    }
+
+    #[test]
+    fn pieces_follow_layout() {
+        let (_lock, _renderer) = test_renderer();
+
+        let mut scrambler = Scrambler::from_seed(15);
+        for size in MIN_SIZE..=MAX_SIZE {
+            let mut cube = Cube::new(size);
+            for mv in scrambler.random_moves(size, 500).moves() {
+                cube.queue_turn(mv);
+
+                // the last frame of the animation is where the turn ends
+                let turn = cube.turns.back().unwrap();
+                turn.show(&mut cube.pieces, 1.0);
+                for (&i, end) in turn.pieces.iter().zip(&turn.end) {
+                    assert!(cube.pieces[i].transform.rot.angle_to(end) < 1.0e-4);
+                }
+            }
+            cube.skip_animation();
+
+            for (i, piece) in cube.pieces.iter().enumerate() {
+                let rot = model_rotation(cube.layout.orientation(i));
+                let error = piece.transform.rot.angle_to(&rot);
+                assert!(error < 1.0e-4, "{}x{} piece {}", size, size, i);
+            }
+        }
     }
 }
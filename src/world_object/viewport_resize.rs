@@ -12,7 +12,7 @@ impl WorldObject for ViewportResize {
         self.viewport.use_viewport();
     }
 
-    fn on_event(&mut self, _evemt: &Event) {}
+    fn on_event(&mut self, _evemt: &Event, _renderer: &Renderer) {}
 }
 
 impl ViewportResize {